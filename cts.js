@@ -3,10 +3,9 @@
 const gpu = require("./")
 exports.create = (flags) => gpu
 
-gpu.GPUAdapter.prototype.features = new Set()
-gpu.GPUDevice.prototype.lost = new Promise(() => {})
-gpu.GPUDevice.prototype.pushErrorScope = () => {}
-gpu.GPUDevice.prototype.popErrorScope = () => {}
-
 globalThis.GPUBufferUsage = gpu.GPUBufferUsage
-globalThis.GPUValidationError = class GPUValidationError extends Error {}
+globalThis.GPUColorWrite = gpu.GPUColorWrite
+globalThis.GPUShaderStage = gpu.GPUShaderStage
+globalThis.GPUValidationError = gpu.GPUValidationError
+globalThis.GPUOutOfMemoryError = gpu.GPUOutOfMemoryError
+globalThis.GPUInternalError = gpu.GPUInternalError
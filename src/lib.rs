@@ -1,21 +1,62 @@
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{
+    Env, JsArrayBuffer, JsFunction, JsNull, JsObject, JsUnknown, NapiRaw,
+    NapiValue, Ref,
+};
 use napi_derive::napi;
 use static_assertions::const_assert_eq;
 use std::cell::RefCell;
-use std::num::NonZeroU32;
+use std::collections::HashMap;
+use std::future::Future;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::panic::AssertUnwindSafe;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::task::{Context, Poll, Waker};
+
+#[napi(catch_unwind)]
+pub async fn request_adapter(
+    options: Option<GPURequestAdapterOptions>,
+) -> napi::Result<Option<GPUAdapter>> {
+    catch_unwind_async(request_adapter_impl(options)).await
+}
 
-#[napi]
-pub async fn request_adapter() -> Option<GPUAdapter> {
+async fn request_adapter_impl(
+    options: Option<GPURequestAdapterOptions>,
+) -> napi::Result<Option<GPUAdapter>> {
+    let power_preference = match options
+        .as_ref()
+        .and_then(|options| options.power_preference.as_deref())
+    {
+        Some(s) => serde_plain::from_str(s).map_err(into_napi_error)?,
+        None => wgpu::PowerPreference::default(),
+    };
+    let force_fallback_adapter = options
+        .and_then(|options| options.force_fallback_adapter)
+        .unwrap_or(false);
+    let options = wgpu::RequestAdapterOptions {
+        power_preference,
+        force_fallback_adapter,
+        compatible_surface: None,
+    };
     let backends = wgpu::Backends::all();
     let instance = wgpu::Instance::new(backends);
-    let options = wgpu::RequestAdapterOptions::default();
-    instance
+    let adapter = instance
         .request_adapter(&options)
         .await
         .map(Arc::new)
-        .map(GPUAdapter)
+        .map(GPUAdapter);
+    Ok(adapter)
+}
+
+#[napi(object)]
+pub struct GPURequestAdapterOptions {
+    pub power_preference: Option<String>,
+    pub force_fallback_adapter: Option<bool>,
 }
 
 #[napi(js_name = "GPUAdapter")]
@@ -23,55 +64,919 @@ pub struct GPUAdapter(Arc<wgpu::Adapter>);
 
 #[napi]
 impl GPUAdapter {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 
-    #[napi(getter)]
+    #[napi(getter, catch_unwind)]
     pub fn get_name(&self) -> String {
         self.0.get_info().name
     }
 
-    #[napi(getter)]
+    #[napi(getter, catch_unwind)]
     pub fn get_is_fallback_adapter(&self) -> bool {
-        false // TODO
+        self.0.get_info().device_type == wgpu::DeviceType::Cpu
     }
 
-    #[napi]
-    pub async fn request_device(&self) -> napi::Result<GPUDevice> {
-        let descriptor = wgpu::DeviceDescriptor::default();
-        self.0
-            .request_device(&descriptor, None)
+    #[napi(getter, catch_unwind)]
+    pub fn get_info(&self) -> GPUAdapterInfo {
+        GPUAdapterInfo(self.0.get_info())
+    }
+
+    #[napi(catch_unwind)]
+    pub async fn request_adapter_info(&self) -> napi::Result<GPUAdapterInfo> {
+        catch_unwind_async(async { Ok(GPUAdapterInfo(self.0.get_info())) })
             .await
-            .map_err(into_napi_error)
-            .map(|(device, queue)| {
-                let queue = Arc::new(queue);
-                GPUDevice { device, queue }
-            })
     }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_features(&self, env: Env) -> napi::Result<JsObject> {
+        features_to_js(env, self.0.features())
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_limits(&self) -> GPUSupportedLimits {
+        GPUSupportedLimits(self.0.limits())
+    }
+
+    #[napi(ts_return_type = "Promise<GPUDevice>", catch_unwind)]
+    pub fn request_device(
+        &self,
+        env: Env,
+        descriptor: Option<GPUDeviceDescriptor>,
+    ) -> napi::Result<JsObject> {
+        let adapter = Arc::clone(&self.0);
+        let features_and_limits = descriptor
+            .as_ref()
+            .map(|descriptor| descriptor.features_and_limits(&adapter))
+            .unwrap_or_else(|| Ok(Default::default()));
+        // wgpu doesn't support queue labels, descriptor.default_queue is ignored.
+        let label = descriptor.and_then(|descriptor| descriptor.label);
+        let future = catch_unwind_async(async move {
+            let (features, limits) = match features_and_limits {
+                Ok(features_and_limits) => features_and_limits,
+                Err(err) => return Ok(Err(err)),
+            };
+            let descriptor = wgpu::DeviceDescriptor {
+                label: label.as_deref(),
+                features,
+                limits,
+            };
+            let result = adapter
+                .request_device(&descriptor, None)
+                .await
+                .map_err(|err| JsException::OperationError(err.to_string()));
+            Ok(result)
+        });
+        env.execute_tokio_future(future, |env, result| {
+            let (device, queue) = result.map_err(|err| err.into_napi(env))?;
+            GPUDevice::create(*env, device, queue)
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUDeviceDescriptor {
+    pub label: Option<String>,
+    pub required_features: Option<Vec<String>>,
+    pub required_limits: Option<HashMap<String, u32>>,
+    pub default_queue: Option<GPUQueueDescriptor>,
+}
+
+impl GPUDeviceDescriptor {
+    fn features_and_limits(
+        &self,
+        adapter: &wgpu::Adapter,
+    ) -> std::result::Result<(wgpu::Features, wgpu::Limits), JsException> {
+        let mut features = wgpu::Features::empty();
+        for name in self.required_features.iter().flatten() {
+            let feature = FEATURES
+                .iter()
+                .find(|&&(s, _)| s == name)
+                .map(|&(_, feature)| feature)
+                .ok_or_else(|| {
+                    let message = format!("bad feature name: {}", name);
+                    JsException::TypeError(message)
+                })?;
+            if !adapter.features().contains(feature) {
+                let message = format!("unsupported feature: {}", name);
+                return Err(JsException::TypeError(message));
+            }
+            features |= feature;
+        }
+        let supported = adapter.limits();
+        let mut limits = wgpu::Limits::default();
+        for (name, &value) in self.required_limits.iter().flatten() {
+            let supported = limit(&supported, name);
+            let limit = limit_mut(&mut limits, name);
+            let (limit, supported) = match (limit, supported) {
+                (Some(limit), Some(supported)) => (limit, supported),
+                _ => {
+                    let message = format!("bad limit name: {}", name);
+                    return Err(JsException::OperationError(message));
+                }
+            };
+            // Alignments are the only "min" limits.
+            let is_alignment = name.starts_with("min");
+            if is_alignment && !value.is_power_of_two() {
+                let message = format!("{} must be a power of two", name);
+                return Err(JsException::OperationError(message));
+            }
+            let better = if is_alignment {
+                value < supported
+            } else {
+                value > supported
+            };
+            if better {
+                let message = format!("unsupported limit: {}", name);
+                return Err(JsException::OperationError(message));
+            }
+            // Values worse than the default limits are raised to them.
+            *limit = if is_alignment {
+                value.min(*limit)
+            } else {
+                value.max(*limit)
+            };
+        }
+        Ok((features, limits))
+    }
+}
+
+#[napi(object)]
+pub struct GPUQueueDescriptor {
+    pub label: Option<String>,
+}
+
+#[napi(js_name = "GPUAdapterInfo")]
+pub struct GPUAdapterInfo(wgpu::AdapterInfo);
+
+#[napi]
+impl GPUAdapterInfo {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_vendor(&self) -> String {
+        format!("{:#06x}", self.0.vendor)
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_architecture(&self) -> String {
+        String::new() // Not reported by wgpu.
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_device(&self) -> String {
+        format!("{:#06x}", self.0.device)
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_description(&self) -> String {
+        self.0.name.clone()
+    }
+
+    // Non-standard.
+    #[napi(getter, catch_unwind)]
+    pub fn get_backend(&self) -> &'static str {
+        match self.0.backend {
+            wgpu::Backend::Empty => "empty",
+            wgpu::Backend::Vulkan => "vulkan",
+            wgpu::Backend::Metal => "metal",
+            wgpu::Backend::Dx12 => "dx12",
+            wgpu::Backend::Dx11 => "dx11",
+            wgpu::Backend::Gl => "gl",
+            wgpu::Backend::BrowserWebGpu => "browser-webgpu",
+        }
+    }
+
+    // Non-standard.
+    #[napi(getter, catch_unwind)]
+    pub fn get_device_type(&self) -> &'static str {
+        match self.0.device_type {
+            wgpu::DeviceType::Other => "other",
+            wgpu::DeviceType::IntegratedGpu => "integrated-gpu",
+            wgpu::DeviceType::DiscreteGpu => "discrete-gpu",
+            wgpu::DeviceType::VirtualGpu => "virtual-gpu",
+            wgpu::DeviceType::Cpu => "cpu",
+        }
+    }
+}
+
+#[napi(js_name = "GPUSupportedLimits")]
+pub struct GPUSupportedLimits(wgpu::Limits);
+
+#[napi]
+impl GPUSupportedLimits {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+
+    #[napi(getter, js_name = "maxTextureDimension1D", catch_unwind)]
+    pub fn get_max_texture_dimension_1d(&self) -> u32 {
+        self.0.max_texture_dimension_1d
+    }
+
+    #[napi(getter, js_name = "maxTextureDimension2D", catch_unwind)]
+    pub fn get_max_texture_dimension_2d(&self) -> u32 {
+        self.0.max_texture_dimension_2d
+    }
+
+    #[napi(getter, js_name = "maxTextureDimension3D", catch_unwind)]
+    pub fn get_max_texture_dimension_3d(&self) -> u32 {
+        self.0.max_texture_dimension_3d
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_texture_array_layers(&self) -> u32 {
+        self.0.max_texture_array_layers
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_bind_groups(&self) -> u32 {
+        self.0.max_bind_groups
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_dynamic_uniform_buffers_per_pipeline_layout(&self) -> u32 {
+        self.0.max_dynamic_uniform_buffers_per_pipeline_layout
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_dynamic_storage_buffers_per_pipeline_layout(&self) -> u32 {
+        self.0.max_dynamic_storage_buffers_per_pipeline_layout
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_sampled_textures_per_shader_stage(&self) -> u32 {
+        self.0.max_sampled_textures_per_shader_stage
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_samplers_per_shader_stage(&self) -> u32 {
+        self.0.max_samplers_per_shader_stage
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_storage_buffers_per_shader_stage(&self) -> u32 {
+        self.0.max_storage_buffers_per_shader_stage
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_storage_textures_per_shader_stage(&self) -> u32 {
+        self.0.max_storage_textures_per_shader_stage
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_uniform_buffers_per_shader_stage(&self) -> u32 {
+        self.0.max_uniform_buffers_per_shader_stage
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_uniform_buffer_binding_size(&self) -> u32 {
+        self.0.max_uniform_buffer_binding_size
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_storage_buffer_binding_size(&self) -> u32 {
+        self.0.max_storage_buffer_binding_size
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_min_uniform_buffer_offset_alignment(&self) -> u32 {
+        self.0.min_uniform_buffer_offset_alignment
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_min_storage_buffer_offset_alignment(&self) -> u32 {
+        self.0.min_storage_buffer_offset_alignment
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_vertex_buffers(&self) -> u32 {
+        self.0.max_vertex_buffers
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_vertex_attributes(&self) -> u32 {
+        self.0.max_vertex_attributes
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_vertex_buffer_array_stride(&self) -> u32 {
+        self.0.max_vertex_buffer_array_stride
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_inter_stage_shader_components(&self) -> u32 {
+        self.0.max_inter_stage_shader_components
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_compute_workgroup_storage_size(&self) -> u32 {
+        self.0.max_compute_workgroup_storage_size
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_compute_invocations_per_workgroup(&self) -> u32 {
+        self.0.max_compute_invocations_per_workgroup
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_compute_workgroup_size_x(&self) -> u32 {
+        self.0.max_compute_workgroup_size_x
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_compute_workgroup_size_y(&self) -> u32 {
+        self.0.max_compute_workgroup_size_y
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_max_compute_workgroup_size_z(&self) -> u32 {
+        self.0.max_compute_workgroup_size_z
+    }
+}
+
+fn limit(limits: &wgpu::Limits, name: &str) -> Option<u32> {
+    limit_mut(&mut limits.clone(), name).copied()
+}
+
+fn limit_mut<'a>(
+    limits: &'a mut wgpu::Limits,
+    name: &str,
+) -> Option<&'a mut u32> {
+    let limit = match name {
+        "maxTextureDimension1D" => &mut limits.max_texture_dimension_1d,
+        "maxTextureDimension2D" => &mut limits.max_texture_dimension_2d,
+        "maxTextureDimension3D" => &mut limits.max_texture_dimension_3d,
+        "maxTextureArrayLayers" => &mut limits.max_texture_array_layers,
+        "maxBindGroups" => &mut limits.max_bind_groups,
+        "maxDynamicUniformBuffersPerPipelineLayout" => {
+            &mut limits.max_dynamic_uniform_buffers_per_pipeline_layout
+        }
+        "maxDynamicStorageBuffersPerPipelineLayout" => {
+            &mut limits.max_dynamic_storage_buffers_per_pipeline_layout
+        }
+        "maxSampledTexturesPerShaderStage" => {
+            &mut limits.max_sampled_textures_per_shader_stage
+        }
+        "maxSamplersPerShaderStage" => {
+            &mut limits.max_samplers_per_shader_stage
+        }
+        "maxStorageBuffersPerShaderStage" => {
+            &mut limits.max_storage_buffers_per_shader_stage
+        }
+        "maxStorageTexturesPerShaderStage" => {
+            &mut limits.max_storage_textures_per_shader_stage
+        }
+        "maxUniformBuffersPerShaderStage" => {
+            &mut limits.max_uniform_buffers_per_shader_stage
+        }
+        "maxUniformBufferBindingSize" => {
+            &mut limits.max_uniform_buffer_binding_size
+        }
+        "maxStorageBufferBindingSize" => {
+            &mut limits.max_storage_buffer_binding_size
+        }
+        "minUniformBufferOffsetAlignment" => {
+            &mut limits.min_uniform_buffer_offset_alignment
+        }
+        "minStorageBufferOffsetAlignment" => {
+            &mut limits.min_storage_buffer_offset_alignment
+        }
+        "maxVertexBuffers" => &mut limits.max_vertex_buffers,
+        "maxVertexAttributes" => &mut limits.max_vertex_attributes,
+        "maxVertexBufferArrayStride" => {
+            &mut limits.max_vertex_buffer_array_stride
+        }
+        "maxInterStageShaderComponents" => {
+            &mut limits.max_inter_stage_shader_components
+        }
+        "maxComputeWorkgroupStorageSize" => {
+            &mut limits.max_compute_workgroup_storage_size
+        }
+        "maxComputeInvocationsPerWorkgroup" => {
+            &mut limits.max_compute_invocations_per_workgroup
+        }
+        "maxComputeWorkgroupSizeX" => &mut limits.max_compute_workgroup_size_x,
+        "maxComputeWorkgroupSizeY" => &mut limits.max_compute_workgroup_size_y,
+        "maxComputeWorkgroupSizeZ" => &mut limits.max_compute_workgroup_size_z,
+        _ => return None,
+    };
+    Some(limit)
+}
+
+const FEATURES: &[(&str, wgpu::Features)] = &[
+    ("depth-clip-control", wgpu::Features::DEPTH_CLIP_CONTROL),
+    (
+        "texture-compression-bc",
+        wgpu::Features::TEXTURE_COMPRESSION_BC,
+    ),
+    (
+        "texture-compression-etc2",
+        wgpu::Features::TEXTURE_COMPRESSION_ETC2,
+    ),
+    (
+        "texture-compression-astc",
+        wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR,
+    ),
+    ("timestamp-query", wgpu::Features::TIMESTAMP_QUERY),
+    (
+        "pipeline-statistics-query",
+        wgpu::Features::PIPELINE_STATISTICS_QUERY,
+    ),
+    (
+        "indirect-first-instance",
+        wgpu::Features::INDIRECT_FIRST_INSTANCE,
+    ),
+    ("shader-f64", wgpu::Features::SHADER_FLOAT64),
+];
+
+// Returns a Set of feature names, i.e., a GPUSupportedFeatures setlike.
+fn features_to_js(
+    env: Env,
+    features: wgpu::Features,
+) -> napi::Result<JsObject> {
+    let mut names = env.create_empty_array()?;
+    let mut index = 0;
+    for &(name, feature) in FEATURES {
+        if features.contains(feature) {
+            names.set_element(index, env.create_string(name)?)?;
+            index += 1;
+        }
+    }
+    new_global_instance(&env, "Set", &[names])
 }
 
-#[napi(js_name = "GPUDevice")]
+#[napi(js_name = "GPUDevice", custom_finalize)]
 pub struct GPUDevice {
-    device: wgpu::Device,
+    device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
+    state: Arc<GPUDeviceState>,
+    lost: Option<Deferred>,
+    events: Arc<GPUDeviceEvents>,
+}
+
+impl GPUDevice {
+    fn create(
+        env: Env,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    ) -> napi::Result<ClassInstance<Self>> {
+        let queue = Arc::new(queue);
+        let state = Arc::new(GPUDeviceState::default());
+        let events = Arc::new(GPUDeviceEvents::default());
+        // The events are dispatched from the callback, not by calling a JS
+        // function, because the tsfn would keep that function alive.
+        let noop = env.create_function_from_closure("noop", |ctx| {
+            ctx.env.get_undefined()
+        })?;
+        let mut tsfn: ThreadsafeFunction<GPUError, ErrorStrategy::Fatal> = noop
+            .create_threadsafe_function(0, {
+                let events = Arc::clone(&events);
+                move |ctx| {
+                    events.dispatch_uncaptured_error(ctx.env, ctx.value)?;
+                    Ok(Vec::<JsUnknown>::new())
+                }
+            })?;
+        tsfn.unref(&env)?;
+        *state.uncaptured_errors.lock().unwrap() = Some(tsfn);
+        device.on_uncaptured_error({
+            let state = Arc::clone(&state);
+            move |err| state.report_error(GPUError::from(err))
+        });
+        let (lost, promise) = Deferred::new(env)?;
+        let lost = Some(lost);
+        let device = Self {
+            device: Arc::new(device),
+            queue,
+            state,
+            lost,
+            events: Arc::clone(&events),
+        };
+        let device = device.into_instance(env)?;
+        let mut object = device.as_object(env);
+        let lost = Property::new("lost")?.with_value(&promise);
+        object.define_properties(&[lost])?;
+        // Only the device object holds on to its EventTarget.
+        let mut event_target =
+            new_global_instance::<JsUnknown>(&env, "EventTarget", &[])?;
+        let bound_listeners =
+            new_global_instance::<JsUnknown>(&env, "WeakMap", &[])?;
+        event_target.set_named_property(BOUND_LISTENERS, bound_listeners)?;
+        let key = env.create_symbol(Some("events"))?;
+        object.set_property(key, &event_target)?;
+        events.init(env, &object, &event_target)?;
+        // For `device instanceof EventTarget`. The device's own
+        // addEventListener(), etc. shadow EventTarget's.
+        let global = env.get_global()?;
+        let object_class = global.get_named_property::<JsObject>("Object")?;
+        let event_target_class =
+            global.get_named_property::<JsObject>("EventTarget")?;
+        let get_prototype_of =
+            object_class.get_named_property::<JsFunction>("getPrototypeOf")?;
+        let prototype =
+            get_prototype_of.call(Some(&object_class), &[&object])?;
+        let parent =
+            event_target_class.get_named_property::<JsUnknown>("prototype")?;
+        let set_prototype_of =
+            object_class.get_named_property::<JsFunction>("setPrototypeOf")?;
+        set_prototype_of.call(Some(&object_class), &[prototype, parent])?;
+        Ok(device)
+    }
+
+    // EventTarget calls listeners with itself as `this`. Returns `listener`
+    // bound to the device instead, creating the bound function if `bind` is
+    // true. Returns `listener` as-is if it isn't a function.
+    fn bound_listener(
+        &self,
+        env: Env,
+        listener: JsUnknown,
+        bind: bool,
+    ) -> napi::Result<JsUnknown> {
+        if listener.get_type()? != ValueType::Function {
+            return Ok(listener);
+        }
+        let (device, events) = self.events.get(env)?;
+        let bound_listeners =
+            events.get_named_property::<JsObject>(BOUND_LISTENERS)?;
+        let get = bound_listeners.get_named_property::<JsFunction>("get")?;
+        let bound = get.call(Some(&bound_listeners), &[&listener])?;
+        if bound.get_type()? == ValueType::Function {
+            return Ok(bound);
+        }
+        if !bind {
+            return Ok(listener);
+        }
+        let function = unsafe { listener.cast::<JsObject>() };
+        let bind = function.get_named_property::<JsFunction>("bind")?;
+        let bound = bind.call(Some(&function), &[&device])?;
+        let set = bound_listeners.get_named_property::<JsFunction>("set")?;
+        set.call(Some(&bound_listeners), &[&listener, &bound])?;
+        Ok(bound)
+    }
+
+    fn call_events_method(
+        &self,
+        env: Env,
+        name: &str,
+        args: &[&JsUnknown],
+    ) -> napi::Result<JsUnknown> {
+        let (_, events) = self.events.get(env)?;
+        let method = events.get_named_property::<JsFunction>(name)?;
+        method.call(Some(&events), args)
+    }
+
+    // Runs `f` inside wgpu error scopes and passes the errors on to our own
+    // error scopes. Returns false if `f` produced an invalid object. Objects
+    // created after destroy() are invalid, like those of a lost device.
+    fn check_errors<T>(&self, f: impl FnOnce(&wgpu::Device) -> T) -> (T, bool) {
+        self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        // Pop the scopes even if `f` panics, a stray scope would swallow
+        // later errors.
+        let result =
+            std::panic::catch_unwind(AssertUnwindSafe(|| f(&self.device)));
+        let mut valid = true;
+        for _ in 0..2 {
+            if let Some(Some(err)) = ready(self.device.pop_error_scope()) {
+                self.state.report_error(GPUError::from(err));
+                valid = false;
+            }
+        }
+        let valid = valid && !self.state.is_destroyed();
+        match result {
+            Ok(result) => (result, valid),
+            Err(err) => std::panic::resume_unwind(err),
+        }
+    }
+
+    fn bind_group_layouts(&self, valid: bool) -> GPUPipelineBindGroupLayouts {
+        GPUPipelineBindGroupLayouts {
+            device: Arc::clone(&self.device),
+            state: Arc::clone(&self.state),
+            valid,
+        }
+    }
+}
+
+impl ObjectFinalize for GPUDevice {
+    fn finalize(self, env: Env) -> napi::Result<()> {
+        self.events.delete(env)
+    }
+}
+
+// Weak references to the device object and its EventTarget. Strong ones
+// would keep the device alive when a listener closes over it.
+#[derive(Default)]
+struct GPUDeviceEvents(Mutex<Option<(WeakRef, WeakRef)>>);
+
+impl GPUDeviceEvents {
+    fn init(
+        &self,
+        env: Env,
+        device: &JsObject,
+        events: &JsObject,
+    ) -> napi::Result<()> {
+        let device = WeakRef::new(env, device)?;
+        let events = WeakRef::new(env, events)?;
+        *self.0.lock().unwrap() = Some((device, events));
+        Ok(())
+    }
+
+    fn get(&self, env: Env) -> napi::Result<(JsObject, JsObject)> {
+        let refs = self.0.lock().unwrap();
+        let (device, events) = match &*refs {
+            Some((device, events)) => (device.get(env)?, events.get(env)?),
+            None => (None, None),
+        };
+        match (device, events) {
+            (Some(device), Some(events)) => Ok((device, events)),
+            _ => Err(into_napi_error("device finalized")),
+        }
+    }
+
+    fn dispatch_uncaptured_error(
+        &self,
+        env: Env,
+        error: GPUError,
+    ) -> napi::Result<()> {
+        // Nothing is listening anymore if the device is gone.
+        if let Ok((device, events)) = self.get(env) {
+            let event = new_uncaptured_error_event(env, &device, error)?;
+            let dispatch_event =
+                events.get_named_property::<JsFunction>("dispatchEvent")?;
+            dispatch_event.call(Some(&events), &[event])?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, env: Env) -> napi::Result<()> {
+        if let Some((device, events)) = self.0.lock().unwrap().take() {
+            device.delete(env)?;
+            events.delete(env)?;
+        }
+        Ok(())
+    }
+}
+
+// State shared between a device and the objects created from it.
+#[derive(Default)]
+struct GPUDeviceState {
+    destroyed: AtomicBool,
+    buffers: Mutex<Vec<Weak<GPUBufferMapping>>>,
+    textures: Mutex<Vec<Weak<GPUTextureResource>>>,
+    error_scopes: Mutex<Vec<GPUErrorScope>>,
+    uncaptured_errors: Mutex<Option<UncapturedErrors>>,
+}
+
+type UncapturedErrors = ThreadsafeFunction<GPUError, ErrorStrategy::Fatal>;
+
+struct GPUErrorScope {
+    filter: GPUErrorFilter,
+    error: Option<GPUError>,
+}
+
+impl GPUDeviceState {
+    fn is_destroyed(&self) -> bool {
+        self.destroyed.load(Ordering::SeqCst)
+    }
+
+    fn track_buffer(&self, buffer: &Arc<GPUBufferMapping>) {
+        let mut buffers = self.buffers.lock().unwrap();
+        buffers.retain(|buffer| buffer.strong_count() > 0);
+        buffers.push(Arc::downgrade(buffer));
+    }
+
+    fn track_texture(&self, texture: &Arc<GPUTextureResource>) {
+        let mut textures = self.textures.lock().unwrap();
+        textures.retain(|texture| texture.strong_count() > 0);
+        textures.push(Arc::downgrade(texture));
+    }
+
+    fn push_error_scope(&self, filter: GPUErrorFilter) {
+        let scope = GPUErrorScope {
+            filter,
+            error: None,
+        };
+        self.error_scopes.lock().unwrap().push(scope);
+    }
+
+    fn pop_error_scope(&self) -> Option<GPUErrorScope> {
+        self.error_scopes.lock().unwrap().pop()
+    }
+
+    // Returns the error if no error scope captured it.
+    fn capture_error(&self, error: GPUError) -> Option<GPUError> {
+        let mut error_scopes = self.error_scopes.lock().unwrap();
+        let scope = error_scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.filter == error.filter());
+        match scope {
+            Some(scope) => {
+                if scope.error.is_none() {
+                    scope.error = Some(error);
+                }
+                None
+            }
+            None => Some(error),
+        }
+    }
+
+    // Sends errors that no error scope captured to the uncapturederror event.
+    // Destroyed devices don't report errors.
+    fn report_error(&self, error: GPUError) {
+        if self.is_destroyed() {
+            return;
+        }
+        if let Some(error) = self.capture_error(error) {
+            let uncaptured_errors = self.uncaptured_errors.lock().unwrap();
+            if let Some(tsfn) = &*uncaptured_errors {
+                tsfn.call(error, ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+    }
+
+    // Destroys as many resources as possible. Returns the first error.
+    fn destroy(&self, env: Env) -> napi::Result<()> {
+        self.destroyed.store(true, Ordering::SeqCst);
+        let mut result = Ok(());
+        let buffers = std::mem::take(&mut *self.buffers.lock().unwrap());
+        for mapping in buffers.iter().filter_map(Weak::upgrade) {
+            result = result.and(mapping.destroy(env));
+        }
+        let textures = std::mem::take(&mut *self.textures.lock().unwrap());
+        for texture in textures.iter().filter_map(Weak::upgrade) {
+            texture.destroy();
+        }
+        result
+    }
 }
 
 #[napi]
 impl GPUDevice {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 
-    #[napi(getter)]
+    #[napi(getter, catch_unwind)]
+    pub fn get_features(&self, env: Env) -> napi::Result<JsObject> {
+        features_to_js(env, self.device.features())
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_limits(&self) -> GPUSupportedLimits {
+        GPUSupportedLimits(self.device.limits())
+    }
+
+    #[napi(getter, catch_unwind)]
     pub fn queue(&self) -> GPUQueue {
         let queue = Arc::clone(&self.queue);
-        GPUQueue(queue)
+        let state = Arc::clone(&self.state);
+        GPUQueue { queue, state }
+    }
+
+    #[napi(catch_unwind)]
+    pub fn add_event_listener(
+        &self,
+        env: Env,
+        event_type: String,
+        listener: JsUnknown,
+        options: Option<JsUnknown>,
+    ) -> napi::Result<()> {
+        let event_type = env.create_string(&event_type)?.into_unknown();
+        let options = match options {
+            Some(options) => options,
+            None => env.get_undefined()?.into_unknown(),
+        };
+        let listener = self.bound_listener(env, listener, true)?;
+        let args = [&event_type, &listener, &options];
+        self.call_events_method(env, "addEventListener", &args)?;
+        Ok(())
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
+    pub fn remove_event_listener(
+        &self,
+        env: Env,
+        event_type: String,
+        listener: JsUnknown,
+        options: Option<JsUnknown>,
+    ) -> napi::Result<()> {
+        let event_type = env.create_string(&event_type)?.into_unknown();
+        let options = match options {
+            Some(options) => options,
+            None => env.get_undefined()?.into_unknown(),
+        };
+        let listener = self.bound_listener(env, listener, false)?;
+        let args = [&event_type, &listener, &options];
+        self.call_events_method(env, "removeEventListener", &args)?;
+        Ok(())
+    }
+
+    #[napi(catch_unwind)]
+    pub fn dispatch_event(
+        &self,
+        env: Env,
+        event: JsUnknown,
+    ) -> napi::Result<bool> {
+        self.call_events_method(env, "dispatchEvent", &[&event])?
+            .coerce_to_bool()?
+            .get_value()
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_onuncapturederror(&self, env: Env) -> napi::Result<JsUnknown> {
+        // The handler is stored on the EventTarget, see GPUDeviceEvents.
+        let (_, events) = self.events.get(env)?;
+        let handler = events.get_named_property::<JsUnknown>(ON_UNCAPTURED)?;
+        match handler.get_type()? {
+            ValueType::Function => Ok(handler),
+            _ => env.get_null().map(JsNull::into_unknown),
+        }
+    }
+
+    #[napi(setter, catch_unwind)]
+    pub fn set_onuncapturederror(
+        &self,
+        env: Env,
+        handler: JsUnknown,
+    ) -> napi::Result<()> {
+        let (_, mut events) = self.events.get(env)?;
+        let event_type = env.create_string("uncapturederror")?.into_unknown();
+        let old_handler = self.get_onuncapturederror(env)?;
+        if old_handler.get_type()? == ValueType::Function {
+            let old_handler = self.bound_listener(env, old_handler, false)?;
+            let args = [&event_type, &old_handler];
+            self.call_events_method(env, "removeEventListener", &args)?;
+            events.set_named_property(ON_UNCAPTURED, env.get_null()?)?;
+        }
+        if handler.get_type()? == ValueType::Function {
+            // The getter returns the unbound handler.
+            events.set_named_property(ON_UNCAPTURED, handler)?;
+            let handler = self.get_onuncapturederror(env)?;
+            let bound = self.bound_listener(env, handler, true)?;
+            let args = [&event_type, &bound];
+            self.call_events_method(env, "addEventListener", &args)?;
+        }
+        Ok(())
+    }
+
+    #[napi(catch_unwind)]
+    pub fn push_error_scope(
+        &self,
+        env: Env,
+        filter: String,
+    ) -> napi::Result<()> {
+        let filter = match filter.as_str() {
+            "validation" => GPUErrorFilter::Validation,
+            "out-of-memory" => GPUErrorFilter::OutOfMemory,
+            "internal" => GPUErrorFilter::Internal,
+            _ => {
+                let message = format!("bad error filter: {}", filter);
+                return Err(JsException::TypeError(message).into_napi(&env));
+            }
+        };
+        self.state.push_error_scope(filter);
+        Ok(())
+    }
+
+    #[napi(ts_return_type = "Promise<GPUError | null>", catch_unwind)]
+    pub fn pop_error_scope(&self, env: Env) -> napi::Result<JsObject> {
+        let (deferred, promise) = Deferred::new(env)?;
+        let scope = self.state.pop_error_scope();
+        if self.state.is_destroyed() {
+            deferred.resolve(env, Null)?;
+        } else if let Some(scope) = scope {
+            deferred.resolve(env, scope.error.map(GPUError::into_js))?;
+        } else {
+            let message = "error scope stack is empty".to_string();
+            let err = JsException::OperationError(message).into_napi(&env);
+            deferred.reject(env, err)?;
+        }
+        Ok(promise)
+    }
+
+    #[napi(catch_unwind)]
+    pub fn destroy(&mut self, env: Env) -> napi::Result<()> {
+        let result = self.state.destroy(env);
+        if let Some(lost) = self.lost.take() {
+            let info = GPUDeviceLostInfo {
+                reason: "destroyed",
+                message: "device destroyed".to_string(),
+            };
+            lost.resolve(env, info)?;
+        }
+        result
+    }
+
+    #[napi(catch_unwind)]
     pub fn create_shader_module(
         &self,
         descriptor: GPUShaderModuleDescriptor,
@@ -79,42 +984,193 @@ impl GPUDevice {
         let label = descriptor.label.as_deref();
         let source = wgpu::ShaderSource::Wgsl(descriptor.code.into());
         let descriptor = wgpu::ShaderModuleDescriptor { label, source };
-        GPUShaderModule(self.device.create_shader_module(&descriptor))
+        let (module, _) = self
+            .check_errors(|device| device.create_shader_module(&descriptor));
+        GPUShaderModule(module)
+    }
+
+    #[napi(catch_unwind)]
+    pub fn create_bind_group_layout(
+        &self,
+        descriptor: GPUBindGroupLayoutDescriptor,
+    ) -> napi::Result<GPUBindGroupLayout> {
+        let label = descriptor.label.as_deref();
+        let mut entries = vec![];
+        for entry in &descriptor.entries {
+            let entry = wgpu::BindGroupLayoutEntry::try_from(entry)?;
+            entries.push(entry);
+        }
+        let descriptor = wgpu::BindGroupLayoutDescriptor {
+            label,
+            entries: &entries,
+        };
+        let (layout, _) = self.check_errors(|device| {
+            device.create_bind_group_layout(&descriptor)
+        });
+        Ok(GPUBindGroupLayout(layout))
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
+    pub fn create_sampler(
+        &self,
+        descriptor: Option<GPUSamplerDescriptor>,
+    ) -> napi::Result<GPUSampler> {
+        let descriptor = descriptor.unwrap_or_default();
+        let label = descriptor.label.as_deref();
+        let address_mode = |mode: &Option<String>| {
+            from_str_or(mode.as_deref(), wgpu::AddressMode::ClampToEdge)
+        };
+        let filter_mode = |mode: &Option<String>| {
+            from_str_or(mode.as_deref(), wgpu::FilterMode::Nearest)
+        };
+        let compare = match descriptor.compare.as_deref() {
+            Some(s) => Some(serde_plain::from_str(s).map_err(into_napi_error)?),
+            None => None,
+        };
+        let mag_filter = filter_mode(&descriptor.mag_filter)?;
+        let min_filter = filter_mode(&descriptor.min_filter)?;
+        let mipmap_filter = filter_mode(&descriptor.mipmap_filter)?;
+        let linear = [mag_filter, min_filter, mipmap_filter]
+            .iter()
+            .all(|filter| *filter == wgpu::FilterMode::Linear);
+        // wgpu only takes powers of two up to 16.
+        let anisotropy_clamp = match descriptor.max_anisotropy.unwrap_or(1) {
+            0 => {
+                let message = "maxAnisotropy must be at least 1".to_string();
+                self.state.report_error(GPUError::Validation(message));
+                None
+            }
+            1 => None,
+            _ if !linear => {
+                let message =
+                    "maxAnisotropy > 1 requires linear filters".to_string();
+                self.state.report_error(GPUError::Validation(message));
+                None
+            }
+            n => NonZeroU8::new(1 << n.min(16).ilog2()),
+        };
+        let descriptor = wgpu::SamplerDescriptor {
+            label,
+            address_mode_u: address_mode(&descriptor.address_mode_u)?,
+            address_mode_v: address_mode(&descriptor.address_mode_v)?,
+            address_mode_w: address_mode(&descriptor.address_mode_w)?,
+            mag_filter,
+            min_filter,
+            mipmap_filter,
+            lod_min_clamp: descriptor.lod_min_clamp.unwrap_or(0.0) as f32,
+            lod_max_clamp: descriptor.lod_max_clamp.unwrap_or(32.0) as f32,
+            compare,
+            anisotropy_clamp,
+            border_color: None,
+        };
+        let (sampler, _) =
+            self.check_errors(|device| device.create_sampler(&descriptor));
+        Ok(GPUSampler(sampler))
+    }
+
+    #[napi(catch_unwind)]
+    pub fn create_bind_group(
+        &self,
+        descriptor: GPUBindGroupDescriptor,
+    ) -> GPUBindGroup {
+        let label = descriptor.label.as_deref();
+        let entries: Vec<_> = descriptor
+            .entries
+            .iter()
+            .map(wgpu::BindGroupEntry::from)
+            .collect();
+        let descriptor = wgpu::BindGroupDescriptor {
+            label,
+            layout: &descriptor.layout.0,
+            entries: &entries,
+        };
+        let (bind_group, _) =
+            self.check_errors(|device| device.create_bind_group(&descriptor));
+        GPUBindGroup(Rc::new(bind_group))
+    }
+
+    #[napi(catch_unwind)]
     pub fn create_pipeline_layout(
         &self,
         descriptor: GPUPipelineLayoutDescriptor,
     ) -> GPUPipelineLayout {
         let label = descriptor.label.as_deref();
+        let bind_group_layouts: Vec<_> = descriptor
+            .bind_group_layouts
+            .iter()
+            .map(|layout| &layout.0)
+            .collect();
         let descriptor = wgpu::PipelineLayoutDescriptor {
             label,
-            bind_group_layouts: &[], // TODO
+            bind_group_layouts: &bind_group_layouts,
             push_constant_ranges: &[],
         };
-        GPUPipelineLayout(self.device.create_pipeline_layout(&descriptor))
+        let (layout, _) = self
+            .check_errors(|device| device.create_pipeline_layout(&descriptor));
+        GPUPipelineLayout(layout)
+    }
+
+    #[napi(catch_unwind)]
+    pub fn create_compute_pipeline(
+        &self,
+        descriptor: GPUComputePipelineDescriptor,
+    ) -> napi::Result<GPUComputePipeline> {
+        let label = descriptor.label.as_deref();
+        let layout = pipeline_layout(&descriptor.layout)?;
+        let descriptor = wgpu::ComputePipelineDescriptor {
+            label,
+            layout,
+            module: &descriptor.compute.module.0,
+            entry_point: &descriptor.compute.entry_point,
+        };
+        let (pipeline, valid) = self
+            .check_errors(|device| device.create_compute_pipeline(&descriptor));
+        let layouts = self.bind_group_layouts(valid);
+        Ok(GPUComputePipeline(Rc::new(pipeline), layouts))
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn create_render_pipeline(
         &self,
         descriptor: GPURenderPipelineDescriptor,
     ) -> napi::Result<GPURenderPipeline> {
         let label = descriptor.label.as_deref();
-        let layout = descriptor.layout.map(|layout| &layout.0);
+        let layout = pipeline_layout(&descriptor.layout)?;
+        let vertex_attributes = descriptor
+            .vertex
+            .buffers
+            .iter()
+            .flatten()
+            .map(|buffer| {
+                buffer.attributes.iter().map(TryFrom::try_from).collect()
+            })
+            .collect::<napi::Result<Vec<Vec<_>>>>()?;
+        let vertex_buffers = descriptor
+            .vertex
+            .buffers
+            .iter()
+            .flatten()
+            .zip(&vertex_attributes)
+            .map(|(buffer, attributes)| {
+                Ok(wgpu::VertexBufferLayout {
+                    array_stride: buffer.array_stride.into(),
+                    step_mode: from_str_or(
+                        buffer.step_mode.as_deref(),
+                        wgpu::VertexStepMode::Vertex,
+                    )?,
+                    attributes,
+                })
+            })
+            .collect::<napi::Result<Vec<_>>>()?;
         let vertex = wgpu::VertexState {
             module: &descriptor.vertex.module.0,
             entry_point: &descriptor.vertex.entry_point,
-            buffers: &[], // TODO
+            buffers: &vertex_buffers,
         };
         let mut fragment_targets = vec![];
         let fragment = if let Some(fragment) = &descriptor.fragment {
             for target in &fragment.targets {
-                let format: wgpu::TextureFormat =
-                    serde_plain::from_str(&target.format)
-                        .map_err(into_napi_error)?;
-                let target = wgpu::ColorTargetState::from(format);
+                let target = wgpu::ColorTargetState::try_from(target)?;
                 fragment_targets.push(target);
             }
             Some(wgpu::FragmentState {
@@ -125,8 +1181,20 @@ impl GPUDevice {
         } else {
             None
         };
-        let multisample = wgpu::MultisampleState::default();
-        let primitive = wgpu::PrimitiveState::default();
+        let multisample = descriptor
+            .multisample
+            .as_ref()
+            .map(wgpu::MultisampleState::from)
+            .unwrap_or_default();
+        let primitive = match &descriptor.primitive {
+            Some(primitive) => wgpu::PrimitiveState::try_from(primitive)?,
+            None => wgpu::PrimitiveState::default(),
+        };
+        let depth_stencil = descriptor
+            .depth_stencil
+            .as_ref()
+            .map(wgpu::DepthStencilState::try_from)
+            .transpose()?;
         let descriptor = wgpu::RenderPipelineDescriptor {
             label,
             layout,
@@ -134,31 +1202,73 @@ impl GPUDevice {
             fragment,
             multisample,
             primitive,
-            depth_stencil: None,
+            depth_stencil,
             multiview: None,
         };
-        let pipeline = self.device.create_render_pipeline(&descriptor);
-        Ok(GPURenderPipeline(Rc::new(pipeline)))
+        let (pipeline, valid) = self
+            .check_errors(|device| device.create_render_pipeline(&descriptor));
+        let layouts = self.bind_group_layouts(valid);
+        Ok(GPURenderPipeline(Rc::new(pipeline), layouts))
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn create_buffer(
         &self,
+        env: Env,
         descriptor: GPUBufferDescriptor,
     ) -> napi::Result<GPUBuffer> {
+        let mapped_at_creation = descriptor.mapped_at_creation.unwrap_or(false);
+        let size = u64::from(descriptor.size);
+        if mapped_at_creation
+            && !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+        {
+            let message = format!(
+                "size must be a multiple of {} when mappedAtCreation is true",
+                wgpu::COPY_BUFFER_ALIGNMENT,
+            );
+            return Err(JsException::RangeError(message).into_napi(&env));
+        }
         let label = descriptor.label.as_deref();
         let usage = wgpu::BufferUsages::from_bits(descriptor.usage)
             .ok_or_else(|| into_napi_error("bad BufferUsage"))?;
         let descriptor = wgpu::BufferDescriptor {
             label,
             usage,
-            size: descriptor.size.into(),
-            mapped_at_creation: descriptor.mapped_at_creation.unwrap_or(false),
+            size,
+            mapped_at_creation,
         };
-        Ok(GPUBuffer(self.device.create_buffer(&descriptor)))
+        let (buffer, valid) =
+            self.check_errors(|device| device.create_buffer(&descriptor));
+        let buffer = Arc::new(buffer);
+        let map_state = if descriptor.mapped_at_creation {
+            GPUBufferMapState::Mapped {
+                start: 0,
+                end: descriptor.size,
+                host: valid,
+                write: true,
+            }
+        } else {
+            GPUBufferMapState::Unmapped
+        };
+        let mapping = GPUBufferMapping::new(
+            Arc::clone(&buffer),
+            Arc::clone(&self.device),
+            valid,
+            map_state,
+        );
+        let mapping = Arc::new(mapping);
+        let state = Arc::clone(&self.state);
+        state.track_buffer(&mapping);
+        let descriptor = descriptor.map_label(|_| None);
+        Ok(GPUBuffer {
+            buffer,
+            state,
+            descriptor,
+            mapping,
+        })
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn create_texture(
         &self,
         descriptor: GPUTextureDescriptor,
@@ -187,108 +1297,711 @@ impl GPUDevice {
             format,
             usage,
         };
-        Ok(GPUTexture(self.device.create_texture(&descriptor)))
+        let (texture, valid) =
+            self.check_errors(|device| device.create_texture(&descriptor));
+        let texture = Arc::new(GPUTextureResource {
+            texture,
+            destroyed: DestroyOnce::new(valid),
+        });
+        self.state.track_texture(&texture);
+        let descriptor = descriptor.map_label(|_| None);
+        Ok(GPUTexture(texture, descriptor, Arc::clone(&self.state)))
+    }
+
+    #[napi(catch_unwind)]
+    pub fn create_command_encoder(&self) -> GPUCommandEncoder {
+        let descriptor = wgpu::CommandEncoderDescriptor {
+            label: None, // TODO
+        };
+        let (encoder, _) = self
+            .check_errors(|device| device.create_command_encoder(&descriptor));
+        let encoder = Rc::new(RefCell::new(Some(Box::new(encoder))));
+        GPUCommandEncoder(encoder, Arc::clone(&self.state))
+    }
+}
+
+#[napi(js_name = "GPUDeviceLostInfo")]
+pub struct GPUDeviceLostInfo {
+    reason: &'static str,
+    message: String,
+}
+
+#[napi]
+impl GPUDeviceLostInfo {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_reason(&self) -> &'static str {
+        self.reason
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GPUErrorFilter {
+    Validation,
+    OutOfMemory,
+    Internal,
+}
+
+enum GPUError {
+    Validation(String),
+    OutOfMemory(String),
+    #[allow(dead_code)] // wgpu doesn't report internal errors yet.
+    Internal(String),
+}
+
+impl GPUError {
+    fn filter(&self) -> GPUErrorFilter {
+        match self {
+            Self::Validation(_) => GPUErrorFilter::Validation,
+            Self::OutOfMemory(_) => GPUErrorFilter::OutOfMemory,
+            Self::Internal(_) => GPUErrorFilter::Internal,
+        }
+    }
+
+    fn into_js(
+        self,
+    ) -> Either3<GPUValidationError, GPUOutOfMemoryError, GPUInternalError>
+    {
+        match self {
+            Self::Validation(message) => {
+                Either3::A(GPUValidationError { message })
+            }
+            Self::OutOfMemory(message) => {
+                Either3::B(GPUOutOfMemoryError { message })
+            }
+            Self::Internal(message) => Either3::C(GPUInternalError { message }),
+        }
+    }
+}
+
+impl From<wgpu::Error> for GPUError {
+    fn from(err: wgpu::Error) -> Self {
+        match err {
+            wgpu::Error::Validation { description, .. } => {
+                Self::Validation(description)
+            }
+            wgpu::Error::OutOfMemory { source } => {
+                Self::OutOfMemory(source.to_string())
+            }
+        }
+    }
+}
+
+const ON_UNCAPTURED: &str = "onuncapturederror";
+const BOUND_LISTENERS: &str = "boundListeners";
+
+// Creates an Event with an extra `error` property, i.e., a duck-typed
+// GPUUncapturedErrorEvent.
+fn new_uncaptured_error_event(
+    env: Env,
+    device: &JsObject,
+    error: GPUError,
+) -> napi::Result<JsObject> {
+    let event_type = env.create_string("uncapturederror")?;
+    let mut event = new_global_instance(&env, "Event", &[event_type])?;
+    let error = unsafe {
+        let error = ToNapiValue::to_napi_value(env.raw(), error.into_js())?;
+        JsUnknown::from_raw_unchecked(env.raw(), error)
+    };
+    let error = Property::new("error")?
+        .with_value(&error)
+        .with_property_attributes(PropertyAttributes::Enumerable);
+    // The event is dispatched on the EventTarget but targets the device.
+    let target = Property::new("target")?.with_value(device);
+    let current_target = Property::new("currentTarget")?.with_value(device);
+    event.define_properties(&[error, target, current_target])?;
+    Ok(event)
+}
+
+#[napi(js_name = "GPUValidationError")]
+pub struct GPUValidationError {
+    message: String,
+}
+
+#[napi]
+impl GPUValidationError {
+    #[napi(constructor, catch_unwind)]
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+#[napi(js_name = "GPUOutOfMemoryError")]
+pub struct GPUOutOfMemoryError {
+    message: String,
+}
+
+#[napi]
+impl GPUOutOfMemoryError {
+    #[napi(constructor, catch_unwind)]
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+#[napi(js_name = "GPUInternalError")]
+pub struct GPUInternalError {
+    message: String,
+}
+
+#[napi]
+impl GPUInternalError {
+    #[napi(constructor, catch_unwind)]
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+#[napi(js_name = "GPUQueue")]
+pub struct GPUQueue {
+    queue: Arc<wgpu::Queue>,
+    state: Arc<GPUDeviceState>,
+}
+
+#[napi]
+impl GPUQueue {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+
+    #[napi(catch_unwind)]
+    pub fn submit(&self, command_buffers: Vec<&mut GPUCommandBuffer>) {
+        let command_buffers =
+            command_buffers.into_iter().filter_map(|buf| buf.0.take());
+        if self.state.is_destroyed() {
+            // Command buffers are consumed but not executed.
+            command_buffers.for_each(drop);
+        } else {
+            self.queue.submit(command_buffers);
+        }
+    }
+
+    #[napi(
+        catch_unwind,
+        ts_args_type = "buffer: GPUBuffer, bufferOffset: number, data: ArrayBuffer | SharedArrayBuffer | ArrayBufferView, dataOffset?: number, size?: number"
+    )]
+    pub fn write_buffer(
+        &self,
+        env: Env,
+        buffer: &GPUBuffer,
+        buffer_offset: u32,
+        data: JsUnknown,
+        data_offset: Option<u32>,
+        size: Option<u32>,
+    ) -> napi::Result<()> {
+        // Offset and size are in elements for typed arrays, else in bytes.
+        let (bytes, element_size) = buffer_source(&env, &data)?;
+        let count = bytes.len() / element_size;
+        let data_offset = data_offset.unwrap_or(0) as usize;
+        if data_offset > count {
+            let message = "dataOffset out of bounds".to_string();
+            return Err(JsException::OperationError(message).into_napi(&env));
+        }
+        let size = size.map_or(count - data_offset, |size| size as usize);
+        if size > count - data_offset {
+            let message = "size out of bounds".to_string();
+            return Err(JsException::OperationError(message).into_napi(&env));
+        }
+        let start = data_offset * element_size;
+        let end = start + size * element_size;
+        if !(end - start).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize) {
+            let message = "size must be a multiple of 4 bytes".to_string();
+            return Err(JsException::OperationError(message).into_napi(&env));
+        }
+        if self.state.is_destroyed() {
+            return Ok(());
+        }
+        // wgpu panics instead of reporting a validation error.
+        let buffer_offset = buffer_offset.into();
+        let data = &bytes[start..end];
+        let size = data.len() as u64;
+        if let Err(message) = buffer.write_range(buffer_offset, size) {
+            self.state.report_error(GPUError::Validation(message));
+            return Ok(());
+        }
+        self.queue.write_buffer(&buffer.buffer, buffer_offset, data);
+        Ok(())
+    }
+
+    #[napi(
+        catch_unwind,
+        ts_args_type = "destination: GPUImageCopyTexture, data: ArrayBuffer | SharedArrayBuffer | ArrayBufferView, dataLayout: GPUImageDataLayout, size: GPUExtend3d"
+    )]
+    pub fn write_texture(
+        &self,
+        env: Env,
+        destination: GPUImageCopyTexture,
+        data: JsUnknown,
+        data_layout: GPUImageDataLayout,
+        size: GPUExtend3d,
+    ) -> napi::Result<()> {
+        let texture = destination.texture;
+        let destination = wgpu::ImageCopyTexture::try_from(&destination)?;
+        // The layout's offset is in bytes, even for typed arrays.
+        let (data, _) = buffer_source(&env, &data)?;
+        let size = wgpu::Extent3d::from(&size);
+        if self.state.is_destroyed() {
+            return Ok(());
+        }
+        // wgpu panics instead of reporting a validation error.
+        let data_len = data.len() as u64;
+        if let Err(message) =
+            texture.write_range(&destination, &data_layout, data_len, size)
+        {
+            self.state.report_error(GPUError::Validation(message));
+            return Ok(());
+        }
+        let data_layout = wgpu::ImageDataLayout::from(&data_layout);
+        self.queue
+            .write_texture(destination, data, data_layout, size);
+        Ok(())
+    }
+}
+
+#[napi(js_name = "GPUCommandBuffer")]
+pub struct GPUCommandBuffer(Option<wgpu::CommandBuffer>);
+
+#[napi]
+impl GPUCommandBuffer {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+}
+
+#[napi(object)]
+pub struct GPUShaderModuleDescriptor {
+    pub code: String,
+    pub label: Option<String>,
+}
+
+#[napi(js_name = "GPUShaderModule")]
+pub struct GPUShaderModule(wgpu::ShaderModule);
+
+#[napi]
+impl GPUShaderModule {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+}
+
+#[napi(object, object_to_js = false)]
+pub struct GPUPipelineLayoutDescriptor {
+    pub bind_group_layouts: Vec<&'static GPUBindGroupLayout>,
+    pub label: Option<String>,
+}
+
+#[napi(object)]
+pub struct GPUBindGroupLayoutDescriptor {
+    pub label: Option<String>,
+    pub entries: Vec<GPUBindGroupLayoutEntry>,
+}
+
+#[napi(object)]
+pub struct GPUBindGroupLayoutEntry {
+    pub binding: u32,
+    pub visibility: u32,
+    pub buffer: Option<GPUBufferBindingLayout>,
+    pub sampler: Option<GPUSamplerBindingLayout>,
+    pub texture: Option<GPUTextureBindingLayout>,
+    pub storage_texture: Option<GPUStorageTextureBindingLayout>,
+}
+
+impl TryFrom<&GPUBindGroupLayoutEntry> for wgpu::BindGroupLayoutEntry {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUBindGroupLayoutEntry) -> napi::Result<Self> {
+        let visibility = wgpu::ShaderStages::from_bits(that.visibility)
+            .ok_or_else(|| into_napi_error("bad shader stage"))?;
+        let ty = match (
+            &that.buffer,
+            &that.sampler,
+            &that.texture,
+            &that.storage_texture,
+        ) {
+            (Some(v), None, None, None) => wgpu::BindingType::try_from(v)?,
+            (None, Some(v), None, None) => wgpu::BindingType::try_from(v)?,
+            (None, None, Some(v), None) => wgpu::BindingType::try_from(v)?,
+            (None, None, None, Some(v)) => wgpu::BindingType::try_from(v)?,
+            _ => return Err(into_napi_error("bad binding type")),
+        };
+        Ok(Self {
+            binding: that.binding,
+            visibility,
+            ty,
+            count: None,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUBufferBindingLayout {
+    pub r#type: Option<String>,
+    pub has_dynamic_offset: Option<bool>,
+    pub min_binding_size: Option<u32>,
+}
+
+impl TryFrom<&GPUBufferBindingLayout> for wgpu::BindingType {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUBufferBindingLayout) -> napi::Result<Self> {
+        let ty = match that.r#type.as_deref().unwrap_or_default() {
+            "" | "uniform" => wgpu::BufferBindingType::Uniform,
+            "storage" => wgpu::BufferBindingType::Storage { read_only: false },
+            "read-only-storage" => {
+                wgpu::BufferBindingType::Storage { read_only: true }
+            }
+            _ => return Err(into_napi_error("bad buffer binding type")),
+        };
+        let has_dynamic_offset = that.has_dynamic_offset.unwrap_or(false);
+        let min_binding_size = that
+            .min_binding_size
+            .and_then(|size| wgpu::BufferSize::new(size.into()));
+        Ok(Self::Buffer {
+            ty,
+            has_dynamic_offset,
+            min_binding_size,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUSamplerBindingLayout {
+    pub r#type: Option<String>,
+}
+
+impl TryFrom<&GPUSamplerBindingLayout> for wgpu::BindingType {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUSamplerBindingLayout) -> napi::Result<Self> {
+        let ty = from_str_or(
+            that.r#type.as_deref(),
+            wgpu::SamplerBindingType::Filtering,
+        )?;
+        Ok(Self::Sampler(ty))
     }
+}
 
-    #[napi]
-    pub fn create_command_encoder(&self) -> GPUCommandEncoder {
-        let descriptor = wgpu::CommandEncoderDescriptor {
-            label: None, // TODO
+#[napi(object)]
+pub struct GPUTextureBindingLayout {
+    pub sample_type: Option<String>,
+    pub view_dimension: Option<String>,
+    pub multisampled: Option<bool>,
+}
+
+impl TryFrom<&GPUTextureBindingLayout> for wgpu::BindingType {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUTextureBindingLayout) -> napi::Result<Self> {
+        let sample_type = match that.sample_type.as_deref().unwrap_or_default()
+        {
+            "" | "float" => wgpu::TextureSampleType::Float { filterable: true },
+            "unfilterable-float" => {
+                wgpu::TextureSampleType::Float { filterable: false }
+            }
+            "depth" => wgpu::TextureSampleType::Depth,
+            "sint" => wgpu::TextureSampleType::Sint,
+            "uint" => wgpu::TextureSampleType::Uint,
+            _ => return Err(into_napi_error("bad texture sample type")),
         };
-        let encoder = self.device.create_command_encoder(&descriptor);
-        GPUCommandEncoder(Rc::new(RefCell::new(Some(Box::new(encoder)))))
+        let view_dimension = from_str_or(
+            that.view_dimension.as_deref(),
+            wgpu::TextureViewDimension::D2,
+        )?;
+        let multisampled = that.multisampled.unwrap_or(false);
+        Ok(Self::Texture {
+            sample_type,
+            view_dimension,
+            multisampled,
+        })
     }
 }
 
-#[napi(js_name = "GPUQueue")]
-pub struct GPUQueue(Arc<wgpu::Queue>);
+#[napi(object)]
+pub struct GPUStorageTextureBindingLayout {
+    pub access: Option<String>,
+    pub format: String,
+    pub view_dimension: Option<String>,
+}
 
-#[napi]
-impl GPUQueue {
-    #[napi(constructor)]
-    pub fn new() -> napi::Result<Self> {
-        not_a_constructor()
-    }
+impl TryFrom<&GPUStorageTextureBindingLayout> for wgpu::BindingType {
+    type Error = napi::Error;
 
-    #[napi]
-    pub fn submit(&self, command_buffers: Vec<&mut GPUCommandBuffer>) {
-        let command_buffers =
-            command_buffers.into_iter().filter_map(|buf| buf.0.take());
-        self.0.submit(command_buffers)
+    fn try_from(that: &GPUStorageTextureBindingLayout) -> napi::Result<Self> {
+        let access = from_str_or(
+            that.access.as_deref(),
+            wgpu::StorageTextureAccess::WriteOnly,
+        )?;
+        let format =
+            serde_plain::from_str(&that.format).map_err(into_napi_error)?;
+        let view_dimension = from_str_or(
+            that.view_dimension.as_deref(),
+            wgpu::TextureViewDimension::D2,
+        )?;
+        Ok(Self::StorageTexture {
+            access,
+            format,
+            view_dimension,
+        })
     }
 }
 
-#[napi(js_name = "GPUCommandBuffer")]
-pub struct GPUCommandBuffer(Option<wgpu::CommandBuffer>);
+#[napi(js_name = "GPUBindGroupLayout")]
+pub struct GPUBindGroupLayout(wgpu::BindGroupLayout);
 
 #[napi]
-impl GPUCommandBuffer {
-    #[napi(constructor)]
+impl GPUBindGroupLayout {
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 }
 
-#[napi(object)]
-pub struct GPUShaderModuleDescriptor {
-    pub code: String,
+#[napi(object, object_to_js = false)]
+pub struct GPUBindGroupDescriptor {
     pub label: Option<String>,
+    pub layout: &'static GPUBindGroupLayout,
+    pub entries: Vec<GPUBindGroupEntry>,
 }
 
-#[napi(js_name = "GPUShaderModule")]
-pub struct GPUShaderModule(wgpu::ShaderModule);
+#[napi(object, object_to_js = false)]
+pub struct GPUBindGroupEntry {
+    pub binding: u32,
+    pub resource:
+        Either3<&'static GPUSampler, &'static GPUTextureView, GPUBufferBinding>,
+}
+
+impl<'a> From<&'a GPUBindGroupEntry> for wgpu::BindGroupEntry<'a> {
+    fn from(that: &'a GPUBindGroupEntry) -> Self {
+        let resource = match &that.resource {
+            Either3::A(sampler) => wgpu::BindingResource::Sampler(&sampler.0),
+            Either3::B(view) => wgpu::BindingResource::TextureView(&view.0),
+            Either3::C(buffer) => {
+                wgpu::BindingResource::Buffer(wgpu::BufferBinding::from(buffer))
+            }
+        };
+        Self {
+            binding: that.binding,
+            resource,
+        }
+    }
+}
+
+#[napi(object, object_to_js = false)]
+pub struct GPUBufferBinding {
+    pub buffer: &'static GPUBuffer,
+    pub offset: Option<u32>,
+    pub size: Option<u32>,
+}
+
+impl<'a> From<&'a GPUBufferBinding> for wgpu::BufferBinding<'a> {
+    fn from(that: &'a GPUBufferBinding) -> Self {
+        let buffer = &that.buffer.buffer;
+        let offset = that.offset.unwrap_or(0).into();
+        let size = that
+            .size
+            .and_then(|size| wgpu::BufferSize::new(size.into()));
+        Self {
+            buffer,
+            offset,
+            size,
+        }
+    }
+}
+
+#[napi(js_name = "GPUBindGroup")]
+pub struct GPUBindGroup(Rc<wgpu::BindGroup>);
 
 #[napi]
-impl GPUShaderModule {
-    #[napi(constructor)]
+impl GPUBindGroup {
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 }
 
+#[derive(Default)]
 #[napi(object)]
-pub struct GPUPipelineLayoutDescriptor {
-    pub bind_group_layouts: Vec<&'static GPUBindGroupLayout>,
+pub struct GPUSamplerDescriptor {
     pub label: Option<String>,
+    pub address_mode_u: Option<String>,
+    pub address_mode_v: Option<String>,
+    pub address_mode_w: Option<String>,
+    pub mag_filter: Option<String>,
+    pub min_filter: Option<String>,
+    pub mipmap_filter: Option<String>,
+    pub lod_min_clamp: Option<f64>,
+    pub lod_max_clamp: Option<f64>,
+    pub compare: Option<String>,
+    pub max_anisotropy: Option<u32>,
 }
 
-#[napi(js_name = "GPUBindGroupLayout")]
-pub struct GPUBindGroupLayout(wgpu::BindGroupLayout);
+#[napi(js_name = "GPUSampler")]
+pub struct GPUSampler(wgpu::Sampler);
 
 #[napi]
-impl GPUBindGroupLayout {
-    #[napi(constructor)]
+impl GPUSampler {
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 }
 
+// Maps "auto" (or a missing layout) to an implicit pipeline layout.
+fn pipeline_layout(
+    layout: &Option<Either<&'static GPUPipelineLayout, String>>,
+) -> napi::Result<Option<&'static wgpu::PipelineLayout>> {
+    match layout {
+        Some(Either::A(layout)) => Ok(Some(&layout.0)),
+        Some(Either::B(s)) if s == "auto" => Ok(None),
+        Some(Either::B(_)) => Err(into_napi_error("bad pipeline layout")),
+        None => Ok(None),
+    }
+}
+
 #[napi(js_name = "GPUPipelineLayout")]
 pub struct GPUPipelineLayout(wgpu::PipelineLayout);
 
 #[napi]
 impl GPUPipelineLayout {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 }
 
-#[napi(object)]
+#[napi(object, object_to_js = false)]
 pub struct GPURenderPipelineDescriptor {
     pub label: Option<String>,
-    pub layout: Option<&'static GPUPipelineLayout>,
+    pub layout: Option<Either<&'static GPUPipelineLayout, String>>,
     pub vertex: GPUVertexState,
     pub fragment: Option<GPUFragmentState>,
+    pub primitive: Option<GPUPrimitiveState>,
+    pub depth_stencil: Option<GPUDepthStencilState>,
+    pub multisample: Option<GPUMultisampleState>,
+}
+
+#[napi(object)]
+pub struct GPUMultisampleState {
+    pub count: Option<u32>,
+    pub mask: Option<u32>,
+    pub alpha_to_coverage_enabled: Option<bool>,
+}
+
+impl From<&GPUMultisampleState> for wgpu::MultisampleState {
+    fn from(that: &GPUMultisampleState) -> Self {
+        Self {
+            count: that.count.unwrap_or(1),
+            mask: that.mask.unwrap_or(!0).into(),
+            alpha_to_coverage_enabled: that
+                .alpha_to_coverage_enabled
+                .unwrap_or(false),
+        }
+    }
 }
 
 #[napi(object)]
+pub struct GPUPrimitiveState {
+    pub topology: Option<String>,
+    pub strip_index_format: Option<String>,
+    pub front_face: Option<String>,
+    pub cull_mode: Option<String>,
+    pub unclipped_depth: Option<bool>,
+}
+
+impl TryFrom<&GPUPrimitiveState> for wgpu::PrimitiveState {
+    type Error = napi::Error;
+
+    // wgpu validates unclippedDepth against the device's features.
+    fn try_from(that: &GPUPrimitiveState) -> napi::Result<Self> {
+        let cull_mode = match that.cull_mode.as_deref() {
+            None | Some("none") => None,
+            Some(s) => Some(serde_plain::from_str(s).map_err(into_napi_error)?),
+        };
+        Ok(Self {
+            topology: from_str_or(
+                that.topology.as_deref(),
+                wgpu::PrimitiveTopology::TriangleList,
+            )?,
+            strip_index_format: that
+                .strip_index_format
+                .as_deref()
+                .map(serde_plain::from_str)
+                .transpose()
+                .map_err(into_napi_error)?,
+            front_face: from_str_or(
+                that.front_face.as_deref(),
+                wgpu::FrontFace::Ccw,
+            )?,
+            cull_mode,
+            unclipped_depth: that.unclipped_depth.unwrap_or(false),
+            ..Default::default()
+        })
+    }
+}
+
+#[napi(object, object_to_js = false)]
 pub struct GPUVertexState {
     pub module: &'static GPUShaderModule,
     pub entry_point: String,
+    pub buffers: Option<Vec<GPUVertexBufferLayout>>,
 }
 
 #[napi(object)]
+pub struct GPUVertexBufferLayout {
+    pub array_stride: u32,
+    pub step_mode: Option<String>,
+    pub attributes: Vec<GPUVertexAttribute>,
+}
+
+#[napi(object)]
+pub struct GPUVertexAttribute {
+    pub format: String,
+    pub offset: u32,
+    pub shader_location: u32,
+}
+
+impl TryFrom<&GPUVertexAttribute> for wgpu::VertexAttribute {
+    type Error = napi::Error;
+
+    fn try_from(attribute: &GPUVertexAttribute) -> napi::Result<Self> {
+        Ok(Self {
+            // wgpu's lowercase serde names match the WebGPU vertex formats.
+            format: serde_plain::from_str(&attribute.format)
+                .map_err(into_napi_error)?,
+            offset: attribute.offset.into(),
+            shader_location: attribute.shader_location,
+        })
+    }
+}
+
+#[napi(object, object_to_js = false)]
 pub struct GPUFragmentState {
     pub module: &'static GPUShaderModule,
     pub entry_point: String,
@@ -298,17 +2011,248 @@ pub struct GPUFragmentState {
 #[napi(object)]
 pub struct GPUColorTargetState {
     pub format: String,
+    pub blend: Option<GPUBlendState>,
+    pub write_mask: Option<u32>,
+}
+
+impl TryFrom<&GPUColorTargetState> for wgpu::ColorTargetState {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUColorTargetState) -> napi::Result<Self> {
+        let write_mask = that.write_mask.unwrap_or(GPUColorWrite::ALL as u32);
+        Ok(Self {
+            format: serde_plain::from_str(&that.format)
+                .map_err(into_napi_error)?,
+            blend: that
+                .blend
+                .as_ref()
+                .map(wgpu::BlendState::try_from)
+                .transpose()?,
+            write_mask: wgpu::ColorWrites::from_bits(write_mask)
+                .ok_or_else(|| into_napi_error("bad ColorWrite"))?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUBlendState {
+    pub color: GPUBlendComponent,
+    pub alpha: GPUBlendComponent,
+}
+
+impl TryFrom<&GPUBlendState> for wgpu::BlendState {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUBlendState) -> napi::Result<Self> {
+        Ok(Self {
+            color: wgpu::BlendComponent::try_from(&that.color)?,
+            alpha: wgpu::BlendComponent::try_from(&that.alpha)?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUBlendComponent {
+    pub operation: Option<String>,
+    pub src_factor: Option<String>,
+    pub dst_factor: Option<String>,
+}
+
+impl TryFrom<&GPUBlendComponent> for wgpu::BlendComponent {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUBlendComponent) -> napi::Result<Self> {
+        Ok(Self {
+            operation: from_str_or(
+                that.operation.as_deref(),
+                wgpu::BlendOperation::Add,
+            )?,
+            src_factor: from_str_or(
+                that.src_factor.as_deref(),
+                wgpu::BlendFactor::One,
+            )?,
+            dst_factor: from_str_or(
+                that.dst_factor.as_deref(),
+                wgpu::BlendFactor::Zero,
+            )?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUDepthStencilState {
+    pub format: String,
+    pub depth_write_enabled: Option<bool>,
+    pub depth_compare: Option<String>,
+    pub stencil_front: Option<GPUStencilFaceState>,
+    pub stencil_back: Option<GPUStencilFaceState>,
+    pub stencil_read_mask: Option<u32>,
+    pub stencil_write_mask: Option<u32>,
+    pub depth_bias: Option<i32>,
+    pub depth_bias_slope_scale: Option<f64>,
+    pub depth_bias_clamp: Option<f64>,
+}
+
+impl TryFrom<&GPUDepthStencilState> for wgpu::DepthStencilState {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUDepthStencilState) -> napi::Result<Self> {
+        let stencil_face = |face: &Option<GPUStencilFaceState>| match face {
+            Some(face) => wgpu::StencilFaceState::try_from(face),
+            None => Ok(wgpu::StencilFaceState::IGNORE),
+        };
+        Ok(Self {
+            format: serde_plain::from_str(&that.format)
+                .map_err(into_napi_error)?,
+            depth_write_enabled: that.depth_write_enabled.unwrap_or(false),
+            depth_compare: from_str_or(
+                that.depth_compare.as_deref(),
+                wgpu::CompareFunction::Always,
+            )?,
+            stencil: wgpu::StencilState {
+                front: stencil_face(&that.stencil_front)?,
+                back: stencil_face(&that.stencil_back)?,
+                read_mask: that.stencil_read_mask.unwrap_or(!0),
+                write_mask: that.stencil_write_mask.unwrap_or(!0),
+            },
+            bias: wgpu::DepthBiasState {
+                constant: that.depth_bias.unwrap_or(0),
+                slope_scale: that.depth_bias_slope_scale.unwrap_or(0.0) as f32,
+                clamp: that.depth_bias_clamp.unwrap_or(0.0) as f32,
+            },
+        })
+    }
+}
+
+#[napi(object)]
+pub struct GPUStencilFaceState {
+    pub compare: Option<String>,
+    pub fail_op: Option<String>,
+    pub depth_fail_op: Option<String>,
+    pub pass_op: Option<String>,
+}
+
+impl TryFrom<&GPUStencilFaceState> for wgpu::StencilFaceState {
+    type Error = napi::Error;
+
+    fn try_from(that: &GPUStencilFaceState) -> napi::Result<Self> {
+        let op = |op: &Option<String>| {
+            from_str_or(op.as_deref(), wgpu::StencilOperation::Keep)
+        };
+        Ok(Self {
+            compare: from_str_or(
+                that.compare.as_deref(),
+                wgpu::CompareFunction::Always,
+            )?,
+            fail_op: op(&that.fail_op)?,
+            depth_fail_op: op(&that.depth_fail_op)?,
+            pass_op: op(&that.pass_op)?,
+        })
+    }
 }
 
 #[napi(js_name = "GPURenderPipeline")]
-pub struct GPURenderPipeline(Rc<wgpu::RenderPipeline>);
+pub struct GPURenderPipeline(
+    Rc<wgpu::RenderPipeline>,
+    GPUPipelineBindGroupLayouts,
+);
 
 #[napi]
 impl GPURenderPipeline {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+
+    #[napi(catch_unwind)]
+    pub fn get_bind_group_layout(
+        &self,
+        env: Env,
+        index: u32,
+    ) -> napi::Result<GPUBindGroupLayout> {
+        self.1
+            .get(&env, index, || self.0.get_bind_group_layout(index))
+    }
+}
+
+// getBindGroupLayout() checks. wgpu panics on invalid pipelines and on
+// indices past the pipeline layout's bind groups.
+struct GPUPipelineBindGroupLayouts {
+    device: Arc<wgpu::Device>,
+    state: Arc<GPUDeviceState>,
+    valid: bool,
+}
+
+impl GPUPipelineBindGroupLayouts {
+    fn get(
+        &self,
+        env: &Env,
+        index: u32,
+        f: impl FnOnce() -> wgpu::BindGroupLayout,
+    ) -> napi::Result<GPUBindGroupLayout> {
+        let max_bind_groups = self.device.limits().max_bind_groups;
+        if index >= max_bind_groups {
+            let message =
+                format!("index must be less than {}", max_bind_groups);
+            return Err(JsException::RangeError(message).into_napi(env));
+        }
+        let message = if self.valid {
+            // The number of bind groups of "auto" layouts is only known to
+            // wgpu. It panics after validation when `index` is out of range,
+            // without leaving anything in an inconsistent state.
+            match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+                Ok(layout) => return Ok(GPUBindGroupLayout(layout)),
+                Err(_) => format!("no bind group layout at index {}", index),
+            }
+        } else {
+            "invalid pipeline".to_string()
+        };
+        self.state.report_error(GPUError::Validation(message));
+        // Stands in for an invalid layout, which wgpu can't create.
+        let descriptor = wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[],
+        };
+        let layout = self.device.create_bind_group_layout(&descriptor);
+        Ok(GPUBindGroupLayout(layout))
+    }
+}
+
+#[napi(object, object_to_js = false)]
+pub struct GPUComputePipelineDescriptor {
+    pub label: Option<String>,
+    pub layout: Option<Either<&'static GPUPipelineLayout, String>>,
+    pub compute: GPUProgrammableStage,
+}
+
+#[napi(object, object_to_js = false)]
+pub struct GPUProgrammableStage {
+    pub module: &'static GPUShaderModule,
+    pub entry_point: String,
+}
+
+#[napi(js_name = "GPUComputePipeline")]
+pub struct GPUComputePipeline(
+    Rc<wgpu::ComputePipeline>,
+    GPUPipelineBindGroupLayouts,
+);
+
+#[napi]
+impl GPUComputePipeline {
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
+
+    #[napi(catch_unwind)]
+    pub fn get_bind_group_layout(
+        &self,
+        env: Env,
+        index: u32,
+    ) -> napi::Result<GPUBindGroupLayout> {
+        self.1
+            .get(&env, index, || self.0.get_bind_group_layout(index))
+    }
 }
 
 // TODO napi-rs won't let us alias or refer to wgpu::BindUsages::* here
@@ -337,6 +2281,36 @@ pub enum GPUBufferUsage {
 #[rustfmt::skip] const_assert_eq!(GPUBufferUsage::STORAGE as u32, wgpu::BufferUsages::STORAGE.bits());
 #[rustfmt::skip] const_assert_eq!(GPUBufferUsage::INDIRECT as u32, wgpu::BufferUsages::INDIRECT.bits());
 
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[napi(js_name = "GPUColorWrite")]
+pub enum GPUColorWrite {
+    RED = 1,
+    GREEN = 2,
+    BLUE = 4,
+    ALPHA = 8,
+    ALL = 15,
+}
+
+#[rustfmt::skip] const_assert_eq!(GPUColorWrite::RED as u32, wgpu::ColorWrites::RED.bits());
+#[rustfmt::skip] const_assert_eq!(GPUColorWrite::GREEN as u32, wgpu::ColorWrites::GREEN.bits());
+#[rustfmt::skip] const_assert_eq!(GPUColorWrite::BLUE as u32, wgpu::ColorWrites::BLUE.bits());
+#[rustfmt::skip] const_assert_eq!(GPUColorWrite::ALPHA as u32, wgpu::ColorWrites::ALPHA.bits());
+#[rustfmt::skip] const_assert_eq!(GPUColorWrite::ALL as u32, wgpu::ColorWrites::ALL.bits());
+
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[napi(js_name = "GPUShaderStage")]
+pub enum GPUShaderStage {
+    VERTEX = 1,
+    FRAGMENT = 2,
+    COMPUTE = 4,
+}
+
+#[rustfmt::skip] const_assert_eq!(GPUShaderStage::VERTEX as u32, wgpu::ShaderStages::VERTEX.bits());
+#[rustfmt::skip] const_assert_eq!(GPUShaderStage::FRAGMENT as u32, wgpu::ShaderStages::FRAGMENT.bits());
+#[rustfmt::skip] const_assert_eq!(GPUShaderStage::COMPUTE as u32, wgpu::ShaderStages::COMPUTE.bits());
+
 #[napi(object)]
 pub struct GPUBufferDescriptor {
     pub label: Option<String>,
@@ -345,76 +2319,637 @@ pub struct GPUBufferDescriptor {
     pub mapped_at_creation: Option<bool>,
 }
 
-#[napi(js_name = "GPUBuffer")]
-pub struct GPUBuffer(wgpu::Buffer);
+#[napi(js_name = "GPUBuffer", custom_finalize)]
+pub struct GPUBuffer {
+    buffer: Arc<wgpu::Buffer>,
+    state: Arc<GPUDeviceState>,
+    descriptor: wgpu::BufferDescriptor<'static>,
+    mapping: Arc<GPUBufferMapping>,
+}
+
+impl ObjectFinalize for GPUBuffer {
+    fn finalize(self, env: Env) -> napi::Result<()> {
+        self.mapping.release(env, false)?;
+        Ok(())
+    }
+}
+
+// Map state of a buffer and the ArrayBuffers handed out by getMappedRange().
+// Shared with the device so that destroying the device can detach them.
+struct GPUBufferMapping {
+    valid: bool,
+    destroyed: DestroyOnce,
+    state: Mutex<GPUBufferMapState>,
+    ranges: Mutex<Vec<GPUBufferMappedRange>>,
+    buffer: Arc<wgpu::Buffer>,
+    device: Arc<wgpu::Device>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GPUBufferMapState {
+    Unmapped,
+    Pending,
+    // `host` is false when wgpu didn't map the buffer, i.e., for invalid
+    // buffers and empty ranges. getMappedRange() then returns zeroed memory.
+    // `write` is true when unmap() copies the mapped ranges back.
+    Mapped {
+        start: u64,
+        end: u64,
+        host: bool,
+        write: bool,
+    },
+}
+
+struct GPUBufferMappedRange {
+    start: u64,
+    end: u64,
+    array_buffer: Ref<()>,
+}
+
+impl GPUBufferMapping {
+    fn new(
+        buffer: Arc<wgpu::Buffer>,
+        device: Arc<wgpu::Device>,
+        valid: bool,
+        state: GPUBufferMapState,
+    ) -> Self {
+        Self {
+            valid,
+            destroyed: DestroyOnce::new(valid),
+            state: Mutex::new(state),
+            ranges: Mutex::new(vec![]),
+            buffer,
+            device,
+        }
+    }
+
+    // Detaches and drops the mapped ranges. If `flush` is true, ranges of
+    // buffers mapped for writing are first copied back into the buffer.
+    // Returns the previous map state. Mustn't panic when called from
+    // finalizers, i.e., when `flush` is false.
+    fn release(
+        &self,
+        env: Env,
+        flush: bool,
+    ) -> napi::Result<GPUBufferMapState> {
+        let mut state =
+            self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = std::mem::replace(&mut *state, GPUBufferMapState::Unmapped);
+        let mut ranges =
+            self.ranges.lock().unwrap_or_else(PoisonError::into_inner);
+        let ranges = std::mem::take(&mut *ranges);
+        let flush = flush
+            && matches!(
+                state,
+                GPUBufferMapState::Mapped {
+                    host: true,
+                    write: true,
+                    ..
+                }
+            );
+        for mut range in ranges {
+            let array_buffer: JsArrayBuffer =
+                env.get_reference_value(&range.array_buffer)?;
+            let data = array_buffer.into_value()?;
+            // Skips empty ranges and ArrayBuffers that were transferred.
+            let len = (range.end - range.start) as usize;
+            if flush && len > 0 && data.len() == len {
+                let slice = self.buffer.slice(range.start..range.end);
+                slice.get_mapped_range_mut().copy_from_slice(&data);
+            }
+            data.into_raw().detach()?;
+            range.array_buffer.unref(env)?;
+        }
+        Ok(state)
+    }
+
+    fn destroy(&self, env: Env) -> napi::Result<()> {
+        self.release(env, false)?;
+        if self.destroyed.destroy() {
+            self.buffer.destroy();
+        }
+        Ok(())
+    }
+}
+
+// wgpu panics when a resource is destroyed twice or when an invalid resource
+// is destroyed. Both are no-ops in WebGPU.
+struct DestroyOnce(AtomicBool);
+
+impl DestroyOnce {
+    fn new(valid: bool) -> Self {
+        Self(AtomicBool::new(!valid))
+    }
+
+    fn is_destroyed(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    // Returns true if the resource should be destroyed now.
+    fn destroy(&self) -> bool {
+        !self.0.swap(true, Ordering::SeqCst)
+    }
+}
+
+impl GPUBuffer {
+    // Validates a mapAsync() range. Returns the range or an error message.
+    fn map_range(
+        &self,
+        mode: wgpu::MapMode,
+        offset: Option<u32>,
+        size: Option<u32>,
+    ) -> std::result::Result<(u64, u64), String> {
+        if !self.mapping.valid {
+            return Err("invalid buffer".to_string());
+        }
+        if self.mapping.destroyed.is_destroyed() {
+            return Err("buffer destroyed".to_string());
+        }
+        let usage = match mode {
+            wgpu::MapMode::Read => wgpu::BufferUsages::MAP_READ,
+            wgpu::MapMode::Write => wgpu::BufferUsages::MAP_WRITE,
+        };
+        if !self.descriptor.usage.contains(usage) {
+            return Err(format!("buffer usage doesn't contain {:?}", usage));
+        }
+        let start = offset.unwrap_or(0) as u64;
+        if start > self.descriptor.size {
+            return Err("offset out of bounds".to_string());
+        }
+        let end = match size {
+            Some(size) => start + size as u64,
+            None => self.descriptor.size,
+        };
+        if end > self.descriptor.size {
+            return Err("size out of bounds".to_string());
+        }
+        if !start.is_multiple_of(wgpu::MAP_ALIGNMENT) {
+            return Err(format!(
+                "offset must be a multiple of {}",
+                wgpu::MAP_ALIGNMENT,
+            ));
+        }
+        if !(end - start).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(format!(
+                "size must be a multiple of {}",
+                wgpu::COPY_BUFFER_ALIGNMENT,
+            ));
+        }
+        Ok((start, end))
+    }
+
+    // Validates a writeBuffer() destination range.
+    fn write_range(
+        &self,
+        offset: u64,
+        size: u64,
+    ) -> std::result::Result<(), String> {
+        if !self.mapping.valid {
+            return Err("invalid buffer".to_string());
+        }
+        if self.mapping.destroyed.is_destroyed() {
+            return Err("buffer destroyed".to_string());
+        }
+        if *self.mapping.state.lock().unwrap() != GPUBufferMapState::Unmapped {
+            return Err("buffer mapped or pending".to_string());
+        }
+        if !self.descriptor.usage.contains(wgpu::BufferUsages::COPY_DST) {
+            return Err("buffer usage doesn't contain COPY_DST".to_string());
+        }
+        if !offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(format!(
+                "bufferOffset must be a multiple of {}",
+                wgpu::COPY_BUFFER_ALIGNMENT,
+            ));
+        }
+        if offset + size > self.descriptor.size {
+            return Err("write out of bounds".to_string());
+        }
+        Ok(())
+    }
+}
 
 #[napi]
 impl GPUBuffer {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 
-    #[napi]
-    pub fn destroy(&self) {
-        self.0.destroy();
+    #[napi(catch_unwind)]
+    pub fn destroy(&self, env: Env) -> napi::Result<()> {
+        self.mapping.destroy(env)
+    }
+
+    #[napi(catch_unwind)]
+    pub fn unmap(&self, env: Env) -> napi::Result<()> {
+        // Unmapping an unmapped buffer is a no-op, not a validation error.
+        match self.mapping.release(env, true)? {
+            GPUBufferMapState::Unmapped => {}
+            GPUBufferMapState::Mapped { host: false, .. } => {}
+            _ => self.buffer.unmap(),
+        }
+        Ok(())
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_size(&self) -> u32 {
+        self.descriptor.size as u32 // Created from a u32.
     }
 
-    #[napi]
-    pub fn unmap(&self) {
-        self.0.unmap();
+    #[napi(getter, catch_unwind)]
+    pub fn get_usage(&self) -> u32 {
+        self.descriptor.usage.bits()
     }
 
-    #[napi]
+    #[napi(getter, catch_unwind)]
+    pub fn get_map_state(&self) -> &'static str {
+        match *self.mapping.state.lock().unwrap() {
+            GPUBufferMapState::Unmapped => "unmapped",
+            GPUBufferMapState::Pending => "pending",
+            GPUBufferMapState::Mapped { .. } => "mapped",
+        }
+    }
+
+    #[napi(catch_unwind)]
     pub async fn map_async(
         &self,
         mode: u32,
         offset: Option<u32>,
         size: Option<u32>,
     ) -> napi::Result<()> {
-        let mode = match mode {
-            1 => wgpu::MapMode::Read,
-            2 => wgpu::MapMode::Write,
-            _ => return Err(into_napi_error("bad mode")),
-        };
-        let offset = offset.unwrap_or(0) as u64;
-        let slice = if let Some(size) = size {
-            self.0.slice(offset..offset + size as u64)
+        catch_unwind_async(async {
+            let mode = match mode {
+                1 => wgpu::MapMode::Read,
+                2 => wgpu::MapMode::Write,
+                _ => return Err(into_napi_error("bad mode")),
+            };
+            if self.state.is_destroyed() {
+                return Err(into_napi_error("device destroyed"));
+            }
+            let (start, end) = {
+                let mut map_state = self.mapping.state.lock().unwrap();
+                if *map_state != GPUBufferMapState::Unmapped {
+                    return Err(into_napi_error("buffer mapped or pending"));
+                }
+                // Validate before slicing, wgpu panics or never settles the
+                // promise on bad ranges.
+                let (start, end) = match self.map_range(mode, offset, size) {
+                    Ok(range) => range,
+                    Err(message) => {
+                        let error = GPUError::Validation(message.clone());
+                        self.state.report_error(error);
+                        return Err(into_napi_error(message));
+                    }
+                };
+                if start == end {
+                    // wgpu can't map empty ranges.
+                    *map_state = GPUBufferMapState::Mapped {
+                        start,
+                        end,
+                        host: false,
+                        write: false,
+                    };
+                    return Ok(());
+                }
+                *map_state = GPUBufferMapState::Pending;
+                (start, end)
+            };
+            let future = self.buffer.slice(start..end).map_async(mode);
+            // wgpu only runs map callbacks when the device is polled.
+            self.mapping.device.poll(wgpu::Maintain::Wait);
+            let result = future.await;
+            let mut map_state = self.mapping.state.lock().unwrap();
+            // Don't clobber the state when unmap() or destroy() aborted the map.
+            if *map_state == GPUBufferMapState::Pending {
+                *map_state = match result {
+                    Ok(()) => GPUBufferMapState::Mapped {
+                        start,
+                        end,
+                        host: true,
+                        write: mode == wgpu::MapMode::Write,
+                    },
+                    Err(_) => GPUBufferMapState::Unmapped,
+                };
+            }
+            result.map_err(into_napi_error)
+        })
+        .await
+    }
+
+    #[napi(catch_unwind)]
+    pub fn get_mapped_range(
+        &self,
+        env: Env,
+        offset: Option<u32>,
+        size: Option<u32>,
+    ) -> napi::Result<JsArrayBuffer> {
+        let (mapped_start, mapped_end, host) =
+            match *self.mapping.state.lock().unwrap() {
+                GPUBufferMapState::Mapped {
+                    start, end, host, ..
+                } => (start, end, host),
+                _ => return Err(into_napi_error("buffer not mapped")),
+            };
+        let start = offset.unwrap_or(0) as u64;
+        let end = match size {
+            Some(size) => start + size as u64,
+            None => self.descriptor.size.max(start),
+        };
+        if !start.is_multiple_of(8) || !(end - start).is_multiple_of(4) {
+            return Err(into_napi_error("misaligned mapped range"));
+        }
+        if start < mapped_start || end > mapped_end {
+            return Err(into_napi_error("mapped range out of bounds"));
+        }
+        {
+            let ranges = self.mapping.ranges.lock().unwrap();
+            if ranges.iter().any(|r| start < r.end && r.start < end) {
+                return Err(into_napi_error("overlapping mapped range"));
+            }
+        }
+        // A copy, not a view of the mapped memory. JS can transfer the
+        // ArrayBuffer, after which it can't be detached on unmap().
+        let array_buffer = if start == end || !host {
+            env.create_arraybuffer((end - start) as usize)?
         } else {
-            self.0.slice(offset..)
+            let slice = self.mapping.buffer.slice(start..end);
+            let data = slice.get_mapped_range().to_vec();
+            env.create_arraybuffer_with_data(data)?
+        };
+        let array_buffer = array_buffer.into_raw();
+        let range = GPUBufferMappedRange {
+            start,
+            end,
+            array_buffer: env.create_reference(&array_buffer)?,
         };
-        slice.map_async(mode).await.map_err(into_napi_error)
+        self.mapping.ranges.lock().unwrap().push(range);
+        Ok(array_buffer)
     }
 }
 
 #[napi(js_name = "GPUTexture")]
-pub struct GPUTexture(wgpu::Texture);
+pub struct GPUTexture(
+    Arc<GPUTextureResource>,
+    wgpu::TextureDescriptor<'static>,
+    Arc<GPUDeviceState>,
+);
+
+struct GPUTextureResource {
+    texture: wgpu::Texture,
+    destroyed: DestroyOnce,
+}
+
+impl GPUTextureResource {
+    fn destroy(&self) {
+        if self.destroyed.destroy() {
+            self.texture.destroy();
+        }
+    }
+}
+
+impl GPUTexture {
+    // Validates a writeTexture() destination and data layout. `data_len` is
+    // the size of the source data in bytes.
+    fn write_range(
+        &self,
+        destination: &wgpu::ImageCopyTexture,
+        layout: &GPUImageDataLayout,
+        data_len: u64,
+        size: wgpu::Extent3d,
+    ) -> std::result::Result<(), String> {
+        let descriptor = &self.1;
+        if self.0.destroyed.is_destroyed() {
+            return Err("texture invalid or destroyed".to_string());
+        }
+        if !descriptor.usage.contains(wgpu::TextureUsages::COPY_DST) {
+            return Err("texture usage doesn't contain COPY_DST".to_string());
+        }
+        if descriptor.sample_count != 1 {
+            return Err("texture is multisampled".to_string());
+        }
+        let format = descriptor.format;
+        if matches!(
+            format,
+            wgpu::TextureFormat::Depth32Float
+                | wgpu::TextureFormat::Depth24Plus
+                | wgpu::TextureFormat::Depth24PlusStencil8
+        ) {
+            return Err(format!("can't write to {:?} textures", format));
+        }
+        if destination.aspect != wgpu::TextureAspect::All {
+            return Err("bad texture aspect".to_string());
+        }
+        let extent = descriptor
+            .mip_level_size(destination.mip_level)
+            .ok_or_else(|| "mipLevel out of bounds".to_string())?
+            .physical_size(format);
+        let origin = destination.origin;
+        if u64::from(origin.x) + u64::from(size.width) > extent.width.into()
+            || u64::from(origin.y) + u64::from(size.height)
+                > extent.height.into()
+            || u64::from(origin.z) + u64::from(size.depth_or_array_layers)
+                > extent.depth_or_array_layers.into()
+        {
+            return Err("write out of bounds".to_string());
+        }
+        let info = format.describe();
+        let (block_width, block_height) = info.block_dimensions;
+        let block_width = u32::from(block_width);
+        let block_height = u32::from(block_height);
+        if !origin.x.is_multiple_of(block_width)
+            || !origin.y.is_multiple_of(block_height)
+            || !size.width.is_multiple_of(block_width)
+            || !size.height.is_multiple_of(block_height)
+        {
+            return Err("write not aligned to texel blocks".to_string());
+        }
+        let block_size = u64::from(info.block_size);
+        let offset = u64::from(layout.offset.unwrap_or(0));
+        if !offset.is_multiple_of(block_size) {
+            return Err(format!("offset must be a multiple of {}", block_size));
+        }
+        if layout.bytes_per_row == Some(0) {
+            return Err("bytesPerRow must not be 0".to_string());
+        }
+        let width_in_blocks = u64::from(size.width / block_width);
+        let height_in_blocks = u64::from(size.height / block_height);
+        let depth = u64::from(size.depth_or_array_layers);
+        let bytes_in_last_row = block_size * width_in_blocks;
+        let bytes_per_row = match layout.bytes_per_row {
+            Some(bytes_per_row) => u64::from(bytes_per_row),
+            None if height_in_blocks > 1 || depth > 1 => {
+                return Err("bytesPerRow is required".to_string());
+            }
+            None => bytes_in_last_row,
+        };
+        if bytes_per_row < bytes_in_last_row {
+            return Err("bytesPerRow too small".to_string());
+        }
+        let rows_per_image = match layout.rows_per_image {
+            Some(rows_per_image) => u64::from(rows_per_image),
+            None if depth > 1 => {
+                return Err("rowsPerImage is required".to_string());
+            }
+            None => height_in_blocks,
+        };
+        if rows_per_image < height_in_blocks {
+            return Err("rowsPerImage too small".to_string());
+        }
+        let mut required = 0u64;
+        if depth > 1 {
+            required = bytes_per_row
+                .saturating_mul(rows_per_image)
+                .saturating_mul(depth - 1);
+        }
+        if depth > 0 && height_in_blocks > 0 {
+            let bytes_in_last_image =
+                bytes_per_row * (height_in_blocks - 1) + bytes_in_last_row;
+            required = required.saturating_add(bytes_in_last_image);
+        }
+        if offset.saturating_add(required) > data_len {
+            return Err("data out of bounds".to_string());
+        }
+        Ok(())
+    }
+}
 
 #[napi]
 impl GPUTexture {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 
-    #[napi]
-    pub fn create_view(&self) -> GPUTextureView {
-        let descriptor = wgpu::TextureViewDescriptor::default();
-        GPUTextureView(self.0.create_view(&descriptor))
+    #[napi(catch_unwind)]
+    pub fn create_view(
+        &self,
+        descriptor: Option<GPUTextureViewDescriptor>,
+    ) -> napi::Result<GPUTextureView> {
+        if let Some(Err(message)) = descriptor.as_ref().map(|d| d.validate()) {
+            self.2.report_error(GPUError::Validation(message));
+        }
+        let descriptor = match &descriptor {
+            Some(descriptor) => descriptor.try_into()?,
+            None => wgpu::TextureViewDescriptor::default(),
+        };
+        let format = descriptor.format.unwrap_or(self.1.format);
+        let view = self.0.texture.create_view(&descriptor);
+        Ok(GPUTextureView(view, format))
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn destroy(&self) {
         self.0.destroy();
     }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_width(&self) -> u32 {
+        self.1.size.width
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_height(&self) -> u32 {
+        self.1.size.height
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_depth_or_array_layers(&self) -> u32 {
+        self.1.size.depth_or_array_layers
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_mip_level_count(&self) -> u32 {
+        self.1.mip_level_count
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_sample_count(&self) -> u32 {
+        self.1.sample_count
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_dimension(&self) -> napi::Result<String> {
+        serde_plain::to_string(&self.1.dimension).map_err(into_napi_error)
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_format(&self) -> napi::Result<String> {
+        serde_plain::to_string(&self.1.format).map_err(into_napi_error)
+    }
+
+    #[napi(getter, catch_unwind)]
+    pub fn get_usage(&self) -> u32 {
+        self.1.usage.bits()
+    }
+}
+
+#[napi(object)]
+pub struct GPUTextureViewDescriptor {
+    pub label: Option<String>,
+    pub format: Option<String>,
+    pub dimension: Option<String>,
+    pub aspect: Option<String>,
+    pub base_mip_level: Option<u32>,
+    pub mip_level_count: Option<u32>,
+    pub base_array_layer: Option<u32>,
+    pub array_layer_count: Option<u32>,
+}
+
+impl GPUTextureViewDescriptor {
+    // Counts are optional, but an explicit count can't be 0.
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.mip_level_count == Some(0) {
+            return Err("mipLevelCount must not be 0".to_string());
+        }
+        if self.array_layer_count == Some(0) {
+            return Err("arrayLayerCount must not be 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<&'a GPUTextureViewDescriptor>
+    for wgpu::TextureViewDescriptor<'a>
+{
+    type Error = napi::Error;
+
+    fn try_from(that: &'a GPUTextureViewDescriptor) -> napi::Result<Self> {
+        let format = that
+            .format
+            .as_deref()
+            .map(serde_plain::from_str)
+            .transpose()
+            .map_err(into_napi_error)?;
+        let dimension = that
+            .dimension
+            .as_deref()
+            .map(serde_plain::from_str)
+            .transpose()
+            .map_err(into_napi_error)?;
+        let aspect =
+            from_str_or(that.aspect.as_deref(), wgpu::TextureAspect::All)?;
+        // Zero counts are reported by createView(), which then falls back to
+        // the remaining levels and layers.
+        Ok(Self {
+            label: that.label.as_deref(),
+            format,
+            dimension,
+            aspect,
+            base_mip_level: that.base_mip_level.unwrap_or(0),
+            mip_level_count: that.mip_level_count.and_then(NonZeroU32::new),
+            base_array_layer: that.base_array_layer.unwrap_or(0),
+            array_layer_count: that.array_layer_count.and_then(NonZeroU32::new),
+        })
+    }
 }
 
 #[napi(js_name = "GPUTextureView")]
-pub struct GPUTextureView(wgpu::TextureView);
+pub struct GPUTextureView(wgpu::TextureView, wgpu::TextureFormat);
 
 #[napi]
 impl GPUTextureView {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
@@ -466,39 +3001,48 @@ pub enum GPUTextureUsage {
 #[rustfmt::skip] const_assert_eq!(GPUTextureUsage::RENDER_ATTACHMENT as u32, wgpu::TextureUsages::RENDER_ATTACHMENT.bits());
 
 #[napi(js_name = "GPUCommandEncoder")]
-pub struct GPUCommandEncoder(Rc<RefCell<Option<Box<wgpu::CommandEncoder>>>>);
+pub struct GPUCommandEncoder(
+    Rc<RefCell<Option<Box<wgpu::CommandEncoder>>>>,
+    Arc<GPUDeviceState>,
+);
 
 #[napi]
 impl GPUCommandEncoder {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn begin_render_pass(
         &mut self,
         descriptor: GPURenderPassDescriptor,
     ) -> napi::Result<GPURenderPassEncoder> {
+        if let Some(attachment) = &descriptor.depth_stencil_attachment {
+            if let Err(message) = attachment.validate() {
+                self.1.report_error(GPUError::Validation(message));
+                // The pass ignores all commands.
+                return Ok(GPURenderPassEncoder(None));
+            }
+        }
         let mut color_attachments = vec![];
         for c in &descriptor.color_attachments {
             let c = wgpu::RenderPassColorAttachment::try_from(c)?;
             color_attachments.push(c);
         }
+        let depth_stencil_attachment = descriptor
+            .depth_stencil_attachment
+            .as_ref()
+            .map(wgpu::RenderPassDepthStencilAttachment::try_from)
+            .transpose()?;
         let descriptor = wgpu::RenderPassDescriptor {
             label: None, // TODO
             color_attachments: &color_attachments,
-            depth_stencil_attachment: None,
+            depth_stencil_attachment,
         };
 
         let cell = Rc::clone(&self.0);
-        let command_encoder = cell
-            .try_borrow_mut()
-            .map_err(into_napi_error)?
-            .take()
-            .ok_or_else(|| into_napi_error("encoder taken"))?;
-        let command_encoder: *mut wgpu::CommandEncoder =
-            Box::into_raw(command_encoder);
+        let command_encoder = take_command_encoder(&cell)?;
         let render_pass =
             unsafe { &mut *command_encoder }.begin_render_pass(&descriptor);
 
@@ -507,12 +3051,38 @@ impl GPUCommandEncoder {
             render_pass,
             cell,
             pipeline: None,
+            bind_groups: vec![],
         };
 
         Ok(GPURenderPassEncoder(Some(state)))
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
+    pub fn begin_compute_pass(
+        &mut self,
+        descriptor: Option<GPUComputePassDescriptor>,
+    ) -> napi::Result<GPUComputePassEncoder> {
+        let label = descriptor.as_ref().and_then(|d| d.label.as_deref());
+        let descriptor = wgpu::ComputePassDescriptor { label };
+
+        let cell = Rc::clone(&self.0);
+        let command_encoder = take_command_encoder(&cell)?;
+        let compute_pass =
+            unsafe { &mut *command_encoder }.begin_compute_pass(&descriptor);
+
+        let state = GPUComputePassEncoderState {
+            command_encoder,
+            compute_pass,
+            cell,
+            pipeline: None,
+            bind_groups: vec![],
+            indirect_buffers: vec![],
+        };
+
+        Ok(GPUComputePassEncoder(Some(state)))
+    }
+
+    #[napi(catch_unwind)]
     pub fn copy_texture_to_buffer(
         &mut self,
         source: GPUImageCopyTexture,
@@ -531,7 +3101,7 @@ impl GPUCommandEncoder {
         Ok(())
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn finish(&mut self) -> napi::Result<GPUCommandBuffer> {
         let encoder = self
             .0
@@ -544,7 +3114,7 @@ impl GPUCommandEncoder {
     }
 }
 
-#[napi(object)]
+#[napi(object, object_to_js = false)]
 pub struct GPUImageCopyBuffer {
     pub buffer: &'static GPUBuffer,
     pub offset: Option<u32>,
@@ -554,7 +3124,7 @@ pub struct GPUImageCopyBuffer {
 
 impl<'a> From<&'a GPUImageCopyBuffer> for wgpu::ImageCopyBuffer<'a> {
     fn from(that: &GPUImageCopyBuffer) -> Self {
-        let buffer = &that.buffer.0;
+        let buffer = &that.buffer.buffer;
         let offset = that.offset.unwrap_or(0) as u64;
         let bytes_per_row = NonZeroU32::new(that.bytes_per_row);
         let rows_per_image = NonZeroU32::new(that.rows_per_image);
@@ -567,7 +3137,25 @@ impl<'a> From<&'a GPUImageCopyBuffer> for wgpu::ImageCopyBuffer<'a> {
     }
 }
 
+// Unlike GPUImageCopyBuffer, bytesPerRow doesn't have to be a multiple of 256.
 #[napi(object)]
+pub struct GPUImageDataLayout {
+    pub offset: Option<u32>,
+    pub bytes_per_row: Option<u32>,
+    pub rows_per_image: Option<u32>,
+}
+
+impl From<&GPUImageDataLayout> for wgpu::ImageDataLayout {
+    fn from(that: &GPUImageDataLayout) -> Self {
+        Self {
+            offset: that.offset.unwrap_or(0).into(),
+            bytes_per_row: that.bytes_per_row.and_then(NonZeroU32::new),
+            rows_per_image: that.rows_per_image.and_then(NonZeroU32::new),
+        }
+    }
+}
+
+#[napi(object, object_to_js = false)]
 pub struct GPUImageCopyTexture {
     pub texture: &'static GPUTexture,
     pub mip_level: Option<u32>,
@@ -594,7 +3182,7 @@ impl<'a> TryFrom<&'a GPUImageCopyTexture> for wgpu::ImageCopyTexture<'a> {
         };
 
         Ok(Self {
-            texture: &that.texture.0,
+            texture: &that.texture.0.texture,
             mip_level,
             origin,
             aspect,
@@ -634,10 +3222,11 @@ impl From<&GPUOrigin3dDict> for wgpu::Origin3d {
     }
 }
 
-#[napi(object)]
+#[napi(object, object_to_js = false)]
 pub struct GPURenderPassDescriptor {
     pub label: Option<String>,
     pub color_attachments: Vec<GPURenderPassColorAttachment>,
+    pub depth_stencil_attachment: Option<GPURenderPassDepthStencilAttachment>,
 }
 
 #[napi(js_name = "GPURenderPassEncoder")]
@@ -647,34 +3236,88 @@ pub struct GPURenderPassEncoderState {
     command_encoder: *mut wgpu::CommandEncoder,
     render_pass: wgpu::RenderPass<'static>,
     pipeline: Option<Rc<wgpu::RenderPipeline>>,
+    bind_groups: Vec<Rc<wgpu::BindGroup>>,
     cell: Rc<RefCell<Option<Box<wgpu::CommandEncoder>>>>,
 }
 
 impl Drop for GPURenderPassEncoderState {
     fn drop(&mut self) {
-        let command_encoder = unsafe { Box::from_raw(self.command_encoder) };
-        assert!(self.cell.borrow_mut().replace(command_encoder).is_none());
+        restore_command_encoder(&self.cell, self.command_encoder);
+    }
+}
+
+// Takes the command encoder out of its cell for the duration of a pass.
+fn take_command_encoder(
+    cell: &RefCell<Option<Box<wgpu::CommandEncoder>>>,
+) -> napi::Result<*mut wgpu::CommandEncoder> {
+    let command_encoder = cell
+        .try_borrow_mut()
+        .map_err(into_napi_error)?
+        .take()
+        .ok_or_else(|| into_napi_error("encoder taken"))?;
+    Ok(Box::into_raw(command_encoder))
+}
+
+// Hands the command encoder back when a pass ends. Must not panic. If the
+// encoder can't be handed back, leak it because the pass still points to it.
+fn restore_command_encoder(
+    cell: &RefCell<Option<Box<wgpu::CommandEncoder>>>,
+    command_encoder: *mut wgpu::CommandEncoder,
+) {
+    let command_encoder = unsafe { Box::from_raw(command_encoder) };
+    if let Ok(mut cell) = cell.try_borrow_mut() {
+        if cell.is_none() {
+            *cell = Some(command_encoder);
+            return;
+        }
     }
+    Box::leak(command_encoder);
 }
 
 #[napi]
 impl GPURenderPassEncoder {
-    #[napi(constructor)]
+    #[napi(constructor, catch_unwind)]
     pub fn new() -> napi::Result<Self> {
         not_a_constructor()
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn set_pipeline(&'static mut self, pipeline: &GPURenderPipeline) {
         if let Some(state) = &mut self.0 {
-            state.pipeline = Some(Rc::clone(&pipeline.0));
-            state
-                .render_pass
-                .set_pipeline(state.pipeline.as_deref().unwrap())
+            let pipeline = state.pipeline.insert(Rc::clone(&pipeline.0));
+            state.render_pass.set_pipeline(pipeline)
+        }
+    }
+
+    #[napi(catch_unwind)]
+    pub fn set_bind_group(
+        &'static mut self,
+        index: u32,
+        bind_group: &GPUBindGroup,
+        dynamic_offsets: Option<Either<Vec<u32>, Uint32Array>>,
+        dynamic_offsets_data_start: Option<u32>,
+        dynamic_offsets_data_length: Option<u32>,
+    ) -> napi::Result<()> {
+        let dynamic_offsets = dynamic_offsets_slice(
+            &dynamic_offsets,
+            dynamic_offsets_data_start,
+            dynamic_offsets_data_length,
+        )?;
+        if let Some(state) = &mut self.0 {
+            // Keep the bind group alive until the render pass ends.
+            state.bind_groups.push(Rc::clone(&bind_group.0));
+            if let Some(bind_group) = state.bind_groups.last() {
+                state.render_pass.set_bind_group(
+                    index,
+                    bind_group,
+                    dynamic_offsets,
+                );
+            }
         }
+        Ok(())
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn set_viewport(
         &mut self,
         x: f64,
@@ -696,7 +3339,7 @@ impl GPURenderPassEncoder {
         }
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn draw(
         &mut self,
         vertex_count: u32,
@@ -714,13 +3357,116 @@ impl GPURenderPassEncoder {
         }
     }
 
-    #[napi]
+    #[napi(catch_unwind)]
     pub fn end(&mut self) {
         self.0.take();
     }
 }
 
 #[napi(object)]
+pub struct GPUComputePassDescriptor {
+    pub label: Option<String>,
+}
+
+#[napi(js_name = "GPUComputePassEncoder")]
+pub struct GPUComputePassEncoder(Option<GPUComputePassEncoderState>);
+
+pub struct GPUComputePassEncoderState {
+    command_encoder: *mut wgpu::CommandEncoder,
+    compute_pass: wgpu::ComputePass<'static>,
+    pipeline: Option<Rc<wgpu::ComputePipeline>>,
+    bind_groups: Vec<Rc<wgpu::BindGroup>>,
+    indirect_buffers: Vec<Arc<wgpu::Buffer>>,
+    cell: Rc<RefCell<Option<Box<wgpu::CommandEncoder>>>>,
+}
+
+impl Drop for GPUComputePassEncoderState {
+    fn drop(&mut self) {
+        restore_command_encoder(&self.cell, self.command_encoder);
+    }
+}
+
+#[napi]
+impl GPUComputePassEncoder {
+    #[napi(constructor, catch_unwind)]
+    pub fn new() -> napi::Result<Self> {
+        not_a_constructor()
+    }
+
+    #[napi(catch_unwind)]
+    pub fn set_pipeline(&'static mut self, pipeline: &GPUComputePipeline) {
+        if let Some(state) = &mut self.0 {
+            let pipeline = state.pipeline.insert(Rc::clone(&pipeline.0));
+            state.compute_pass.set_pipeline(pipeline)
+        }
+    }
+
+    #[napi(catch_unwind)]
+    pub fn set_bind_group(
+        &'static mut self,
+        index: u32,
+        bind_group: &GPUBindGroup,
+        dynamic_offsets: Option<Either<Vec<u32>, Uint32Array>>,
+        dynamic_offsets_data_start: Option<u32>,
+        dynamic_offsets_data_length: Option<u32>,
+    ) -> napi::Result<()> {
+        let dynamic_offsets = dynamic_offsets_slice(
+            &dynamic_offsets,
+            dynamic_offsets_data_start,
+            dynamic_offsets_data_length,
+        )?;
+        if let Some(state) = &mut self.0 {
+            // Keep the bind group alive until the compute pass ends.
+            state.bind_groups.push(Rc::clone(&bind_group.0));
+            if let Some(bind_group) = state.bind_groups.last() {
+                state.compute_pass.set_bind_group(
+                    index,
+                    bind_group,
+                    dynamic_offsets,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[napi(catch_unwind)]
+    pub fn dispatch_workgroups(
+        &mut self,
+        workgroup_count_x: u32,
+        workgroup_count_y: Option<u32>,
+        workgroup_count_z: Option<u32>,
+    ) {
+        let y = workgroup_count_y.unwrap_or(1);
+        let z = workgroup_count_z.unwrap_or(1);
+        if let Some(state) = &mut self.0 {
+            state.compute_pass.dispatch(workgroup_count_x, y, z);
+        }
+    }
+
+    #[napi(catch_unwind)]
+    pub fn dispatch_workgroups_indirect(
+        &'static mut self,
+        indirect_buffer: &GPUBuffer,
+        indirect_offset: u32,
+    ) {
+        if let Some(state) = &mut self.0 {
+            // Keep the buffer alive until the compute pass ends.
+            let buffer = Arc::clone(&indirect_buffer.buffer);
+            state.indirect_buffers.push(buffer);
+            if let Some(buffer) = state.indirect_buffers.last() {
+                let offset = indirect_offset.into();
+                state.compute_pass.dispatch_indirect(buffer, offset);
+            }
+        }
+    }
+
+    #[napi(catch_unwind)]
+    pub fn end(&mut self) {
+        self.0.take();
+    }
+}
+
+#[napi(object, object_to_js = false)]
 pub struct GPURenderPassColorAttachment {
     pub label: Option<String>,
     pub view: &'static GPUTextureView,
@@ -756,6 +3502,107 @@ impl TryFrom<&GPURenderPassColorAttachment>
     }
 }
 
+#[napi(object, object_to_js = false)]
+pub struct GPURenderPassDepthStencilAttachment {
+    pub view: &'static GPUTextureView,
+    pub depth_clear_value: Option<f64>,
+    pub depth_load_op: Option<String>,
+    pub depth_store_op: Option<String>,
+    pub depth_read_only: Option<bool>,
+    pub stencil_clear_value: Option<u32>,
+    pub stencil_load_op: Option<String>,
+    pub stencil_store_op: Option<String>,
+    pub stencil_read_only: Option<bool>,
+}
+
+impl GPURenderPassDepthStencilAttachment {
+    // Whether the view's format has a depth aspect and a stencil aspect.
+    fn aspects(&self) -> (bool, bool) {
+        match self.view.1 {
+            wgpu::TextureFormat::Depth32Float
+            | wgpu::TextureFormat::Depth24Plus => (true, false),
+            wgpu::TextureFormat::Depth24PlusStencil8 => (true, true),
+            _ => (false, false),
+        }
+    }
+
+    // Writable aspects need both a load op and a store op.
+    fn validate(&self) -> std::result::Result<(), String> {
+        let (has_depth, has_stencil) = self.aspects();
+        if has_depth
+            && !self.depth_read_only.unwrap_or(false)
+            && (self.depth_load_op.is_none() || self.depth_store_op.is_none())
+        {
+            return Err("depthLoadOp and depthStoreOp are required".to_string());
+        }
+        if has_stencil
+            && !self.stencil_read_only.unwrap_or(false)
+            && (self.stencil_load_op.is_none()
+                || self.stencil_store_op.is_none())
+        {
+            return Err(
+                "stencilLoadOp and stencilStoreOp are required".to_string()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&GPURenderPassDepthStencilAttachment>
+    for wgpu::RenderPassDepthStencilAttachment<'static>
+{
+    type Error = napi::Error;
+
+    fn try_from(
+        that: &GPURenderPassDepthStencilAttachment,
+    ) -> napi::Result<Self> {
+        // Read-only aspects, and aspects the format lacks, have no ops.
+        let (has_depth, has_stencil) = that.aspects();
+        let depth_ops = if that.depth_read_only.unwrap_or(false) || !has_depth {
+            None
+        } else {
+            Some(attachment_ops(
+                that.depth_load_op.as_deref(),
+                that.depth_store_op.as_deref(),
+                that.depth_clear_value.unwrap_or(0.0) as f32,
+            )?)
+        };
+        let stencil_ops =
+            if that.stencil_read_only.unwrap_or(false) || !has_stencil {
+                None
+            } else {
+                Some(attachment_ops(
+                    that.stencil_load_op.as_deref(),
+                    that.stencil_store_op.as_deref(),
+                    that.stencil_clear_value.unwrap_or(0),
+                )?)
+            };
+        Ok(Self {
+            view: &that.view.0,
+            depth_ops,
+            stencil_ops,
+        })
+    }
+}
+
+fn attachment_ops<T>(
+    load_op: Option<&str>,
+    store_op: Option<&str>,
+    clear_value: T,
+) -> napi::Result<wgpu::Operations<T>> {
+    let load = match load_op {
+        Some("load") => wgpu::LoadOp::Load,
+        Some("clear") => wgpu::LoadOp::Clear(clear_value),
+        _ => return Err(into_napi_error("bad load op")),
+    };
+    let store = match store_op {
+        Some("store") => true,
+        Some("discard") => false,
+        _ => return Err(into_napi_error("bad store op")),
+    };
+    Ok(wgpu::Operations { load, store })
+}
+
 #[napi]
 pub struct GPUColor(napi::Either<GPUColorDict, Vec<f64>>);
 
@@ -789,6 +3636,255 @@ impl From<&GPUColorDict> for wgpu::Color {
     }
 }
 
+// Errors that must be thrown as a specific JS exception class rather than
+// as a plain Error.
+#[allow(clippy::enum_variant_names)] // Named after the JS classes.
+enum JsException {
+    TypeError(String),
+    RangeError(String),
+    OperationError(String),
+}
+
+impl JsException {
+    fn into_napi(self, env: &Env) -> napi::Error {
+        let (constructor, args) = match &self {
+            Self::TypeError(message) => ("TypeError", vec![message.as_str()]),
+            Self::RangeError(message) => ("RangeError", vec![message.as_str()]),
+            Self::OperationError(message) => {
+                ("DOMException", vec![message.as_str(), "OperationError"])
+            }
+        };
+        let new_instance = || {
+            let args = args
+                .into_iter()
+                .map(|arg| env.create_string(arg))
+                .collect::<napi::Result<Vec<_>>>()?;
+            let exception = new_global_instance(env, constructor, &args)?;
+            Ok(napi::Error::from(exception.into_unknown()))
+        };
+        new_instance().unwrap_or_else(|err| err)
+    }
+}
+
+// A reference that doesn't keep its object alive. Only used on the main
+// thread.
+struct WeakRef(sys::napi_ref);
+
+unsafe impl Send for WeakRef {}
+
+impl WeakRef {
+    fn new(env: Env, object: &JsObject) -> napi::Result<Self> {
+        let mut reference = std::ptr::null_mut();
+        check_status!(unsafe {
+            sys::napi_create_reference(
+                env.raw(),
+                object.raw(),
+                0,
+                &mut reference,
+            )
+        })?;
+        Ok(Self(reference))
+    }
+
+    fn get(&self, env: Env) -> napi::Result<Option<JsObject>> {
+        let mut object = std::ptr::null_mut();
+        check_status!(unsafe {
+            sys::napi_get_reference_value(env.raw(), self.0, &mut object)
+        })?;
+        if object.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(unsafe {
+            JsObject::from_raw_unchecked(env.raw(), object)
+        }))
+    }
+
+    fn delete(self, env: Env) -> napi::Result<()> {
+        check_status!(unsafe { sys::napi_delete_reference(env.raw(), self.0) })
+    }
+}
+
+// A promise that is settled from the main thread. Unlike JsDeferred, it
+// doesn't keep the event loop alive while it's pending.
+struct Deferred(sys::napi_deferred);
+
+impl Deferred {
+    fn new(env: Env) -> napi::Result<(Self, JsObject)> {
+        let mut deferred = std::ptr::null_mut();
+        let mut promise = std::ptr::null_mut();
+        check_status!(unsafe {
+            sys::napi_create_promise(env.raw(), &mut deferred, &mut promise)
+        })?;
+        let promise =
+            unsafe { JsObject::from_raw_unchecked(env.raw(), promise) };
+        Ok((Self(deferred), promise))
+    }
+
+    fn resolve(self, env: Env, value: impl ToNapiValue) -> napi::Result<()> {
+        let value = unsafe { ToNapiValue::to_napi_value(env.raw(), value)? };
+        check_status!(unsafe {
+            sys::napi_resolve_deferred(env.raw(), self.0, value)
+        })
+    }
+
+    fn reject(self, env: Env, err: napi::Error) -> napi::Result<()> {
+        let err = unsafe { ToNapiValue::to_napi_value(env.raw(), err)? };
+        check_status!(unsafe {
+            sys::napi_reject_deferred(env.raw(), self.0, err)
+        })
+    }
+}
+
+// Implements the (offsets, start, length) overload of setBindGroup().
+fn dynamic_offsets_slice(
+    dynamic_offsets: &Option<Either<Vec<u32>, Uint32Array>>,
+    start: Option<u32>,
+    length: Option<u32>,
+) -> napi::Result<&[u32]> {
+    let dynamic_offsets: &[u32] = match dynamic_offsets {
+        Some(Either::A(v)) => v,
+        Some(Either::B(v)) => v,
+        None => &[],
+    };
+    let start = start.unwrap_or(0) as usize;
+    let length = length
+        .map_or(dynamic_offsets.len().saturating_sub(start), |n| n as usize);
+    start
+        .checked_add(length)
+        .and_then(|end| dynamic_offsets.get(start..end))
+        .ok_or_else(|| into_napi_error("bad dynamic offsets range"))
+}
+
+fn from_str_or<T: serde::de::DeserializeOwned>(
+    s: Option<&str>,
+    default: T,
+) -> napi::Result<T> {
+    match s {
+        Some(s) => serde_plain::from_str(s).map_err(into_napi_error),
+        None => Ok(default),
+    }
+}
+
+// Calls `new globalThis[constructor](...args)`.
+fn new_global_instance<V: NapiRaw>(
+    env: &Env,
+    constructor: &str,
+    args: &[V],
+) -> napi::Result<JsObject> {
+    env.get_global()?
+        .get_named_property::<JsFunction>(constructor)?
+        .new_instance(args)
+}
+
+// Returns the contents of an ArrayBuffer, SharedArrayBuffer or ArrayBufferView
+// and the size of its elements, which is 1 unless it's a typed array.
+fn buffer_source<'a>(
+    env: &Env,
+    data: &'a JsUnknown,
+) -> napi::Result<(&'a [u8], usize)> {
+    let mut typedarray_type = 0;
+    let mut data_ptr = std::ptr::null_mut();
+    let mut len = 0;
+    let mut arraybuffer = std::ptr::null_mut();
+    let mut byte_offset = 0;
+    let element_size = if data.is_dataview()? {
+        check_status!(unsafe {
+            sys::napi_get_dataview_info(
+                env.raw(),
+                data.raw(),
+                &mut len,
+                &mut data_ptr,
+                &mut arraybuffer,
+                &mut byte_offset,
+            )
+        })?;
+        1
+    } else {
+        // Wrap (Shared)ArrayBuffers in a Uint8Array, napi can't look
+        // inside a SharedArrayBuffer. The view aliases the buffer's memory.
+        let is_instance_of = |name| -> napi::Result<bool> {
+            let global = env.get_global()?;
+            data.instanceof(global.get_named_property::<JsFunction>(name)?)
+        };
+        let view = if data.is_typedarray()? {
+            unsafe { data.raw() }
+        } else if is_instance_of("ArrayBuffer")?
+            || is_instance_of("SharedArrayBuffer")?
+        {
+            unsafe { new_global_instance(env, "Uint8Array", &[data])?.raw() }
+        } else {
+            let message = "data is not a BufferSource".to_string();
+            return Err(JsException::TypeError(message).into_napi(env));
+        };
+        check_status!(unsafe {
+            sys::napi_get_typedarray_info(
+                env.raw(),
+                view,
+                &mut typedarray_type,
+                &mut len,
+                &mut data_ptr,
+                &mut arraybuffer,
+                &mut byte_offset,
+            )
+        })?;
+        let element_size = match TypedArrayType::from(typedarray_type) {
+            TypedArrayType::Int8
+            | TypedArrayType::Uint8
+            | TypedArrayType::Uint8Clamped => 1,
+            TypedArrayType::Int16 | TypedArrayType::Uint16 => 2,
+            TypedArrayType::Int32
+            | TypedArrayType::Uint32
+            | TypedArrayType::Float32 => 4,
+            TypedArrayType::Float64
+            | TypedArrayType::BigInt64
+            | TypedArrayType::BigUint64 => 8,
+            _ => return Err(into_napi_error("unsupported typed array")),
+        };
+        len *= element_size;
+        element_size
+    };
+    let bytes = if len == 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(data_ptr as *const u8, len) }
+    };
+    Ok((bytes, element_size))
+}
+
+// Polls a future that is known to be ready, like the ones returned by
+// wgpu::Device::pop_error_scope() with the native backend.
+// Polls a future that wgpu's native backend resolves immediately. Returns
+// None if it didn't.
+fn ready<F: Future>(future: F) -> Option<F::Output> {
+    let mut context = Context::from_waker(Waker::noop());
+    match std::pin::pin!(future).poll(&mut context) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+// #[napi(catch_unwind)] only covers the synchronous part of async functions.
+// Turns panics in the future's body into rejections.
+async fn catch_unwind_async<T>(
+    future: impl Future<Output = napi::Result<T>>,
+) -> napi::Result<T> {
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(|cx| {
+        let poll = AssertUnwindSafe(|| future.as_mut().poll(cx));
+        std::panic::catch_unwind(poll).unwrap_or_else(|err| {
+            let message = if let Some(s) = err.downcast_ref::<String>() {
+                s.clone()
+            } else if let Some(s) = err.downcast_ref::<&str>() {
+                s.to_string()
+            } else {
+                "panic from Rust code".to_string()
+            };
+            Poll::Ready(Err(into_napi_error(message)))
+        })
+    })
+    .await
+}
+
 fn not_a_constructor<T>() -> napi::Result<T> {
     Err(into_napi_error("not a constructor"))
 }
@@ -0,0 +1,48 @@
+"use strict"
+
+const assert = require("node:assert")
+const test = require("node:test")
+const gpu = require("../")
+
+const { GPUBufferUsage } = gpu
+
+async function requestDevice(t) {
+    const adapter = await gpu.requestAdapter()
+    if (!adapter) {
+        t.skip("no adapter")
+        return null
+    }
+    return adapter.requestDevice()
+}
+
+test("mapAsync after the last submit settles", async (t) => {
+    const device = await requestDevice(t)
+    if (!device) return
+    const buffer = device.createBuffer({
+        size: 16,
+        usage: GPUBufferUsage.MAP_READ | GPUBufferUsage.COPY_DST,
+    })
+    device.queue.writeBuffer(buffer, 0, new Uint32Array([1, 2, 3, 4]))
+    device.queue.submit([device.createCommandEncoder().finish()])
+    await buffer.mapAsync(1 /* GPUMapMode.READ */)
+    const data = new Uint32Array(buffer.getMappedRange())
+    assert.deepStrictEqual([...data], [1, 2, 3, 4])
+    buffer.unmap()
+    assert.strictEqual(data.length, 0)
+})
+
+test("unmap writes mappedAtCreation ranges back", async (t) => {
+    const device = await requestDevice(t)
+    if (!device) return
+    const buffer = device.createBuffer({
+        size: 16,
+        usage: GPUBufferUsage.MAP_READ,
+        mappedAtCreation: true,
+    })
+    new Uint32Array(buffer.getMappedRange(8)).set([5, 6])
+    buffer.unmap()
+    await buffer.mapAsync(1 /* GPUMapMode.READ */)
+    const data = new Uint32Array(buffer.getMappedRange())
+    assert.deepStrictEqual([...data], [0, 0, 5, 6])
+    buffer.unmap()
+})